@@ -0,0 +1,97 @@
+//! Compare `Rng` and `BatchRng` by counting the calls returning 0 over many
+//! states.
+use dq3::rand::batch::{self, BatchRng, BatchRng256, BatchRng32, BatchRng64, Lanes, SlicedByte};
+use dq3::rand::{Rng, State};
+
+use std::hint::black_box;
+use std::time::Instant;
+
+const STATES: u32 = 1 << 16;
+const CALLS: usize = 64;
+
+fn bench<F: FnMut() -> u64>(name: &str, mut f: F) {
+    let start = Instant::now();
+    let count = black_box(f());
+    let elapsed = start.elapsed();
+    println!(
+        "  {:<20} {:>10.3} ms  {:>6.2} ns/state/call  (count {})",
+        name,
+        elapsed.as_secs_f64() * 1000.0,
+        elapsed.as_nanos() as f64 / (STATES as f64 * CALLS as f64),
+        count
+    );
+}
+
+fn scalar<F: Fn(&mut Rng) -> u8>(f: F) -> u64 {
+    let mut count = 0;
+    for v in 0..STATES {
+        let mut rng = Rng::from(State::new(v));
+        for _ in 0..CALLS {
+            count += (f(&mut rng) == 0) as u64;
+        }
+    }
+    count
+}
+
+fn sliced<L: Lanes, F: Fn(&mut BatchRng<L>) -> SlicedByte<L>>(f: F) -> u64 {
+    let mut count = 0;
+    for first in (0..STATES).step_by(L::LANES) {
+        let mut rng = BatchRng::<L>::from_range(first);
+        for _ in 0..CALLS {
+            count += batch::eq_byte(&f(&mut rng), 0).count() as u64;
+        }
+    }
+    count
+}
+
+fn unsliced<L: Lanes, F: Fn(&mut BatchRng<L>) -> Vec<u8>>(f: F) -> u64 {
+    let mut count = 0;
+    for first in (0..STATES).step_by(L::LANES) {
+        let mut rng = BatchRng::<L>::from_range(first);
+        for _ in 0..CALLS {
+            count += f(&mut rng).iter().filter(|v| **v == 0).count() as u64;
+        }
+    }
+    count
+}
+
+fn main() {
+    println!("{} states x {} calls", STATES, CALLS);
+
+    println!("rand()");
+    bench("Rng", || scalar(|rng| rng.rand()));
+    bench("BatchRng32", || sliced(BatchRng32::rand_sliced));
+    bench("BatchRng64", || sliced(BatchRng64::rand_sliced));
+    bench("BatchRng256", || sliced(BatchRng256::rand_sliced));
+    bench("BatchRng64 unsliced", || unsliced(BatchRng64::rand));
+
+    println!("rand_by_multiply(16)");
+    bench("Rng", || scalar(|rng| rng.rand_by_multiply(16)));
+    bench("BatchRng32", || {
+        sliced(|rng: &mut BatchRng32| rng.rand_by_multiply_sliced(16))
+    });
+    bench("BatchRng64", || {
+        sliced(|rng: &mut BatchRng64| rng.rand_by_multiply_sliced(16))
+    });
+    bench("BatchRng256", || {
+        sliced(|rng: &mut BatchRng256| rng.rand_by_multiply_sliced(16))
+    });
+    bench("BatchRng64 unsliced", || {
+        unsliced(|rng: &mut BatchRng64| rng.rand_by_multiply(16))
+    });
+
+    println!("rand_multinomial(136, 0x1f)");
+    bench("Rng", || scalar(|rng| rng.rand_multinomial(136, 0x1f)));
+    bench("BatchRng32", || {
+        sliced(|rng: &mut BatchRng32| rng.rand_multinomial_sliced(136, 0x1f))
+    });
+    bench("BatchRng64", || {
+        sliced(|rng: &mut BatchRng64| rng.rand_multinomial_sliced(136, 0x1f))
+    });
+    bench("BatchRng256", || {
+        sliced(|rng: &mut BatchRng256| rng.rand_multinomial_sliced(136, 0x1f))
+    });
+    bench("BatchRng64 unsliced", || {
+        unsliced(|rng: &mut BatchRng64| rng.rand_multinomial(136, 0x1f))
+    });
+}
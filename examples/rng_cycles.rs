@@ -0,0 +1,47 @@
+use dq3::rand::analysis;
+use dq3::rand::State;
+
+use std::env;
+
+fn print_cycle_decomposition() {
+    println!("Cycles:");
+    for cycles in analysis::cycle_decomposition() {
+        println!("  period: {:#x}, count: {}", cycles.period, cycles.count);
+    }
+    println!(
+        "Unreachable states (tail length 1): {:#x}",
+        (1_u64 << 32) - analysis::CYCLIC_STATES
+    );
+}
+
+fn print_orbit_relation(x: State, y: State) {
+    println!("Period of the orbit of {}: {:#x}", x, analysis::period(x));
+    println!("Period of the orbit of {}: {:#x}", y, analysis::period(y));
+
+    if !analysis::is_same_orbit(x, y) {
+        println!("{} and {} are on different orbits", x, y);
+        return;
+    }
+
+    match analysis::distance(x, y) {
+        Some(n) => println!("{} reaches {} after {} transitions", x, y, n),
+        None => println!("{} never reaches {}", x, y),
+    }
+    match analysis::distance(y, x) {
+        Some(n) => println!("{} reaches {} after {} transitions", y, x, n),
+        None => println!("{} never reaches {}", y, x),
+    }
+}
+
+/// Usage: rng_cycles [X [Y]]
+///
+/// Print the cycle decomposition of the state space and the relation between
+/// the orbits of X and Y (0xaae21259 by default).
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let x = args.get(1).map_or(State::default(), |s| s.parse().unwrap());
+    let y = args.get(2).map_or(State::default(), |s| s.parse().unwrap());
+
+    print_cycle_decomposition();
+    print_orbit_relation(x, y);
+}
@@ -1,88 +1,441 @@
-use crate::rand::Rng;
+use crate::rand::{RandomSource, Rng};
+
+pub mod accuracy;
+pub mod combatant;
+pub mod order;
+
+use accuracy::Accuracy;
+use combatant::Combatant;
 use num;
+use std::convert::TryFrom;
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Character {
     Player,
     Monster,
 }
 
+/// The position of a combatant in a battle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CombatantId {
+    pub side: Character,
+    pub index: usize,
+}
+
+/// The result of a physical attack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hit {
+    pub damage: i16,
+    /// Whether it is a critical hit (かいしんのいちげき) or a heavy blow
+    /// (つうこんのいちげき).
+    pub critical: bool,
+    /// Whether the attacker missed or the target dodged, dealing no damage.
+    pub missed: bool,
+}
+
+impl CombatantId {
+    pub fn player(index: usize) -> Self {
+        Self {
+            side: Character::Player,
+            index,
+        }
+    }
+
+    pub fn monster(index: usize) -> Self {
+        Self {
+            side: Character::Monster,
+            index,
+        }
+    }
+}
+
 #[wasm_bindgen]
 #[derive(Debug, Clone, Default)]
 pub struct Battle {
     rng: Rng,
+    party: Vec<Combatant>,
+    monsters: Vec<Combatant>,
     // TODO: implement more
 }
 
 #[wasm_bindgen]
+// The bindings call the deprecated `physical_damage`, which stays for JS.
+#[allow(deprecated)]
 impl Battle {
     #[wasm_bindgen(constructor)]
     pub fn new(rng: Rng) -> Self {
-        Self { rng }
+        Self {
+            rng,
+            ..Default::default()
+        }
+    }
+
+    pub fn rng(&self) -> Rng {
+        self.rng.clone()
+    }
+
+    /// See `physical_damage`.
+    #[deprecated(note = "never misses, use `Battle::attack`")]
+    pub fn physical_damage(
+        &mut self,
+        atk: i16,
+        def: i16,
+        twinhits: bool,
+        attacker: Character,
+    ) -> i16 {
+        physical_damage(&mut self.rng, atk, def, twinhits, attacker)
+    }
+}
+
+impl Battle {
+    /// Create a battle between the player's party and the monsters.
+    pub fn with_parties(rng: Rng, party: Vec<Combatant>, monsters: Vec<Combatant>) -> Self {
+        assert!(party.iter().all(|c| c.side == Character::Player));
+        assert!(monsters.iter().all(|c| c.side == Character::Monster));
+        Self {
+            rng,
+            party,
+            monsters,
+        }
     }
 
-    fn physical_damage_0or1(&mut self) -> i16 {
-        return (self.rng.rand() % 2) as i16;
+    pub fn party(&self) -> &[Combatant] {
+        &self.party
     }
 
-    fn physical_damage_defensive(&mut self, atk: i16) -> i16 {
-        return (((atk / 8) as i32) * (self.rng.rand() as i32) / 256) as i16;
+    pub fn monsters(&self) -> &[Combatant] {
+        &self.monsters
     }
 
-    fn physical_damage_normal(&mut self, base: i16) -> i16 {
-        const RAND_LOWER: u8 = 99;
-        const RAND_UPPER: u8 = 153;
-        let rand = num::clamp(self.rng.rand_multinomial(6, 0xf), RAND_LOWER, RAND_UPPER) as i32;
-        return ((base as i32) * rand / 256) as i16;
+    pub fn combatant(&self, id: CombatantId) -> &Combatant {
+        match id.side {
+            Character::Player => &self.party[id.index],
+            Character::Monster => &self.monsters[id.index],
+        }
     }
 
-    fn physical_damage_by_player(&mut self, base: i16) -> i16 {
-        return if base < 2 {
-            self.physical_damage_0or1()
-        } else {
-            self.physical_damage_normal(base)
+    pub fn combatant_mut(&mut self, id: CombatantId) -> &mut Combatant {
+        match id.side {
+            Character::Player => &mut self.party[id.index],
+            Character::Monster => &mut self.monsters[id.index],
+        }
+    }
+
+    /// Return whether every combatant of the side is dead.
+    pub fn is_wiped_out(&self, side: Character) -> bool {
+        let combatants = match side {
+            Character::Player => &self.party,
+            Character::Monster => &self.monsters,
         };
+        combatants.iter().all(|c| !c.is_alive())
     }
 
-    fn physical_damage_by_monster(&mut self, base: i16, atk: i16) -> i16 {
-        return if base <= atk / 8 {
-            if atk < 16 {
-                self.physical_damage_0or1()
-            } else {
-                self.physical_damage_defensive(atk)
-            }
-        } else {
-            if atk < 8 {
-                self.physical_damage_0or1()
-            } else {
-                self.physical_damage_normal(base)
-            }
+    /// Return the ids of the living combatants, the party first.
+    pub fn living(&self) -> Vec<CombatantId> {
+        let party = (0..self.party.len()).map(CombatantId::player);
+        let monsters = (0..self.monsters.len()).map(CombatantId::monster);
+        party
+            .chain(monsters)
+            .filter(|id| self.combatant(*id).is_alive())
+            .collect()
+    }
+
+    /// Roll the order of the living combatants to act in a round.
+    ///
+    /// See `order::roll_order` for `order_value`. The random values are drawn
+    /// for the party first and then for the monsters.
+    pub fn turn_order<F>(&mut self, order_value: F) -> Vec<CombatantId>
+    where
+        F: Fn(u16, u8) -> u32,
+    {
+        let actors: Vec<(CombatantId, u16)> = self
+            .living()
+            .into_iter()
+            .map(|id| (id, self.combatant(id).agility))
+            .collect();
+        order::roll_order(&mut self.rng, &actors, order_value)
+    }
+
+    /// Return the exact probability that `id` acts before `other` in a round,
+    /// see `turn_order`.
+    ///
+    /// A dead combatant never acts, so this is 0.0 if either of them is dead.
+    /// Panics if `id` and `other` are the same.
+    pub fn probability_acts_before<F>(
+        &self,
+        id: CombatantId,
+        other: CombatantId,
+        order_value: F,
+    ) -> f64
+    where
+        F: Fn(u16, u8) -> u32,
+    {
+        assert_ne!(id, other);
+        let living = self.living();
+        let position = |id| living.iter().position(|l| *l == id);
+        let first_on_tie = match (position(id), position(other)) {
+            (Some(i), Some(j)) => i < j,
+            _ => return 0.0,
         };
+        order::probability_acts_before(
+            self.combatant(id).agility,
+            self.combatant(other).agility,
+            first_on_tie,
+            order_value,
+        )
     }
 
-    pub fn physical_damage(
+    /// Return the exact probability that a physical attack by `attacker` hits
+    /// `target`, see `accuracy::hit_probability`.
+    pub fn hit_probability(
+        &self,
+        attacker: CombatantId,
+        target: CombatantId,
+        accuracy: Accuracy,
+    ) -> f64 {
+        accuracy::hit_probability(self.combatant(attacker), self.combatant(target), accuracy)
+    }
+
+    /// Attack `target` physically by `attacker` and apply the damage to it.
+    ///
+    /// See `physical_attack` for `accuracy` and `critical`. The damage of the
+    /// returned hit is the one the target actually took.
+    pub fn attack(
         &mut self,
-        atk: i16,
-        def: i16,
-        twinhits: bool,
-        attacker: Character,
-    ) -> i16 {
-        let base = atk - (def / 2);
-        let damage = match attacker {
-            Character::Player => self.physical_damage_by_player(base),
-            Character::Monster => self.physical_damage_by_monster(base, atk),
+        attacker: CombatantId,
+        target: CombatantId,
+        accuracy: Accuracy,
+        critical: Critical,
+    ) -> Hit {
+        let by = self.combatant(attacker).clone();
+        let to = self.combatant(target).clone();
+        let hit = physical_attack(&mut self.rng, &by, &to, accuracy, critical);
+        self.apply(target, hit)
+    }
+
+    /// Deal a heavy blow (つうこんのいちげき) to `target` by `attacker`, the
+    /// action chosen from the action table of a monster.
+    ///
+    /// This rolls only the damage in `damage`. Whether a heavy blow can miss is
+    /// yet to be taken from the ROM, so roll `accuracy::roll_hit` first if so.
+    pub fn heavy_blow(
+        &mut self,
+        attacker: CombatantId,
+        target: CombatantId,
+        damage: CriticalDamage,
+    ) -> Hit {
+        let atk = self.combatant(attacker).attack;
+        let hit = Hit {
+            damage: damage.roll(&mut self.rng, atk),
+            critical: true,
+            missed: false,
         };
-        let coef = if twinhits { 2 } else { 1 };
-        return coef * damage;
+        self.apply(target, hit)
     }
+
+    fn apply(&mut self, target: CombatantId, hit: Hit) -> Hit {
+        let taken = self
+            .combatant_mut(target)
+            .take_damage(u16::try_from(hit.damage).unwrap_or(0));
+        Hit {
+            damage: taken as i16,
+            ..hit
+        }
+    }
+}
+
+fn physical_damage_0or1<R: RandomSource>(rng: &mut R) -> i16 {
+    return (rng.rand() % 2) as i16;
+}
+
+fn physical_damage_defensive<R: RandomSource>(rng: &mut R, atk: i16) -> i16 {
+    return (((atk / 8) as i32) * (rng.rand() as i32) / 256) as i16;
+}
+
+fn physical_damage_normal<R: RandomSource>(rng: &mut R, base: i16) -> i16 {
+    const RAND_LOWER: u8 = 99;
+    const RAND_UPPER: u8 = 153;
+    let rand = num::clamp(rng.rand_multinomial(6, 0xf), RAND_LOWER, RAND_UPPER) as i32;
+    return ((base as i32) * rand / 256) as i16;
+}
+
+/// See `Critical` for the draw of a critical hit.
+fn physical_damage_by_player<R: RandomSource>(
+    rng: &mut R,
+    base: i16,
+    atk: i16,
+    critical: Critical,
+) -> Hit {
+    if critical.threshold > 0 {
+        rng.set_tag(format_args!("critical"));
+        if rng.rand() < critical.threshold {
+            return Hit {
+                damage: critical.damage.roll(rng, atk),
+                critical: true,
+                missed: false,
+            };
+        }
+    }
+
+    rng.set_tag(format_args!("physical_damage"));
+    let damage = if base < 2 {
+        physical_damage_0or1(rng)
+    } else {
+        physical_damage_normal(rng, base)
+    };
+    Hit {
+        damage,
+        critical: false,
+        missed: false,
+    }
+}
+
+/// A monster never draws for a critical hit, since its heavy blow is an action
+/// of its own, see `Battle::heavy_blow`.
+fn physical_damage_by_monster<R: RandomSource>(rng: &mut R, base: i16, atk: i16) -> i16 {
+    return if base <= atk / 8 {
+        if atk < 16 {
+            physical_damage_0or1(rng)
+        } else {
+            physical_damage_defensive(rng, atk)
+        }
+    } else {
+        if atk < 8 {
+            physical_damage_0or1(rng)
+        } else {
+            physical_damage_normal(rng, base)
+        }
+    };
+}
+
+fn physical_hit<R: RandomSource>(
+    rng: &mut R,
+    atk: i16,
+    def: i16,
+    attacker: Character,
+    critical: Critical,
+) -> Hit {
+    let base = atk - (def / 2);
+    match attacker {
+        Character::Player => physical_damage_by_player(rng, base, atk, critical),
+        Character::Monster => {
+            rng.set_tag(format_args!("physical_damage"));
+            Hit {
+                damage: physical_damage_by_monster(rng, base, atk),
+                critical: false,
+                missed: false,
+            }
+        }
+    }
+}
+
+/// Return the damage of a physical attack, drawing random numbers from `rng`.
+///
+/// This deals no critical hit, and never misses as it knows nothing about the
+/// target but its defense, so use `physical_attack` instead.
+#[deprecated(note = "never misses, use `physical_attack`")]
+pub fn physical_damage<R: RandomSource>(
+    rng: &mut R,
+    atk: i16,
+    def: i16,
+    twinhits: bool,
+    attacker: Character,
+) -> i16 {
+    let damage = physical_hit(rng, atk, def, attacker, Critical::default()).damage;
+    let coef = if twinhits { 2 } else { 1 };
+    return coef * damage;
+}
+
+/// How a player deals a critical hit (かいしんのいちげき).
+///
+/// The rate, e.g. of the Fighter, and the damage are yet to be taken from the
+/// ROM, so the caller provides them. A player with a nonzero `threshold` draws
+/// one `rand()` before the damage, and deals a critical hit if it is below
+/// `threshold`. The default never deals one and draws nothing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Critical {
+    /// The `rand()` threshold, i.e. in 1/256.
+    pub threshold: u8,
+    pub damage: CriticalDamage,
+}
+
+impl Critical {
+    /// Return the probability of a critical hit.
+    pub fn probability(&self) -> f64 {
+        self.threshold as f64 / 256.0
+    }
+}
+
+/// The damage of a critical hit or a heavy blow, which ignores the defense and
+/// is `atk * (lower + rand_by_multiply(spread)) / 256`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CriticalDamage {
+    pub lower: u16,
+    pub spread: u8,
+}
+
+impl CriticalDamage {
+    /// Roll the damage of an attack of `atk`.
+    pub fn roll<R: RandomSource>(&self, rng: &mut R, atk: i16) -> i16 {
+        rng.set_tag(format_args!("critical_damage"));
+        let rand = self.lower as i32 + rng.rand_by_multiply(self.spread) as i32;
+        ((atk as i32) * rand / 256) as i16
+    }
+}
+
+/// Return the result of a physical attack, drawing random numbers from `rng`.
+///
+/// First `accuracy::roll_hit` decides whether it hits by `accuracy`, and
+/// nothing more is drawn on a miss. Then a player may deal a critical hit by
+/// `critical`. A monster ignores it, since its heavy blow is an action of its
+/// own, see `Battle::heavy_blow`.
+pub fn physical_attack<R: RandomSource>(
+    rng: &mut R,
+    attacker: &Combatant,
+    target: &Combatant,
+    accuracy: Accuracy,
+    critical: Critical,
+) -> Hit {
+    if !accuracy::roll_hit(rng, attacker, target, accuracy) {
+        return Hit {
+            damage: 0,
+            critical: false,
+            missed: true,
+        };
+    }
+
+    physical_hit(
+        rng,
+        attacker.attack,
+        target.defense,
+        attacker.side,
+        critical,
+    )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::battle::combatant::CombatantInit;
+    use crate::rand::{SequenceRng, State};
+
+    // Made-up rules to test attacks, not the ones of the game.
+    const ACCURACY: Accuracy = Accuracy {
+        dazzled_hit: 64,
+        skip_no_evasion: true,
+    };
+    const CRITICAL: Critical = Critical {
+        threshold: 20,
+        damage: CriticalDamage {
+            lower: 243,
+            spread: 26,
+        },
+    };
 
     #[test]
+    #[allow(deprecated)]
     fn test_damage_twinhits() {
         let atk = 250;
         let def = 0;
@@ -101,4 +454,268 @@ mod tests {
 
         assert_eq!(damage_normal * 2, damage_twinhits);
     }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_damage_by_sequence() {
+        // The multinomial value is clamped to [99, 153], and nothing is drawn
+        // for a critical hit.
+        let mut rng = SequenceRng::new(vec![0; 16]);
+        assert_eq!(
+            physical_damage(&mut rng, 256, 0, false, Character::Player),
+            99
+        );
+        assert_eq!(rng.consumed(), 16);
+
+        let mut rng = SequenceRng::new(vec![0xff; 16]);
+        assert_eq!(
+            physical_damage(&mut rng, 256, 0, false, Character::Player),
+            153
+        );
+
+        // A defensive damage is (atk / 8) * rand() / 256.
+        let mut rng = SequenceRng::new(vec![128]);
+        assert_eq!(
+            physical_damage(&mut rng, 100, 250, false, Character::Monster),
+            6
+        );
+    }
+
+    #[test]
+    fn test_attack() {
+        let hero = CombatantInit {
+            side: Character::Player,
+            max_hp: 30,
+            attack: 40,
+            defense: 10,
+            ..Default::default()
+        }
+        .init();
+        let slime = CombatantInit {
+            max_hp: 8,
+            attack: 9,
+            defense: 4,
+            ..Default::default()
+        }
+        .init();
+        let mut battle =
+            Battle::with_parties(Rng::default(), vec![hero], vec![slime.clone(), slime]);
+
+        let mut rng = Rng::default();
+        let expected = physical_attack(
+            &mut rng,
+            &battle.party()[0],
+            &battle.monsters()[1],
+            ACCURACY,
+            CRITICAL,
+        );
+        let hit = battle.attack(
+            CombatantId::player(0),
+            CombatantId::monster(1),
+            ACCURACY,
+            CRITICAL,
+        );
+
+        assert_eq!(hit.critical, expected.critical);
+        assert_eq!(hit.damage, i16::min(expected.damage, 8));
+        assert_eq!(battle.monsters()[1].hp, 8 - hit.damage as u16);
+        assert_eq!(battle.monsters()[0].hp, 8);
+        assert_eq!(battle.rng().state(), rng.state());
+        assert_eq!(
+            battle.hit_probability(CombatantId::player(0), CombatantId::monster(0), ACCURACY),
+            1.0
+        );
+
+        battle.attack(
+            CombatantId::player(0),
+            CombatantId::monster(0),
+            ACCURACY,
+            CRITICAL,
+        );
+        battle.attack(
+            CombatantId::player(0),
+            CombatantId::monster(1),
+            ACCURACY,
+            CRITICAL,
+        );
+        assert!(battle.is_wiped_out(Character::Monster));
+        assert!(!battle.is_wiped_out(Character::Player));
+    }
+
+    #[test]
+    fn test_critical_by_sequence() {
+        let player = CombatantInit {
+            side: Character::Player,
+            attack: 100,
+            ..Default::default()
+        }
+        .init();
+        let metal = CombatantInit {
+            defense: 255,
+            evasion: 64,
+            ..Default::default()
+        }
+        .init();
+        assert_eq!(CRITICAL.probability(), 20.0 / 256.0);
+
+        // A critical hit ignores the defense: 100 * (243 + 26) / 256.
+        let mut rng = SequenceRng::new(vec![64, 19, 255]);
+        let hit = physical_attack(&mut rng, &player, &metal, ACCURACY, CRITICAL);
+        assert_eq!(
+            hit,
+            Hit {
+                damage: 105,
+                critical: true,
+                missed: false
+            }
+        );
+        assert_eq!(rng.consumed(), 3);
+
+        // No critical hit: the defense is too high to deal more than 0 or 1.
+        let mut rng = SequenceRng::new(vec![64, 20, 1]);
+        let hit = physical_attack(&mut rng, &player, &metal, ACCURACY, CRITICAL);
+        assert_eq!(
+            hit,
+            Hit {
+                damage: 1,
+                critical: false,
+                missed: false
+            }
+        );
+        assert_eq!(rng.consumed(), 3);
+
+        // Without a threshold nothing is drawn for a critical hit.
+        let mut rng = SequenceRng::new(vec![64, 1]);
+        let hit = physical_attack(&mut rng, &player, &metal, ACCURACY, Critical::default());
+        assert_eq!(hit.damage, 1);
+        assert_eq!(rng.consumed(), 2);
+
+        // A dodge draws nothing more.
+        let mut rng = SequenceRng::new(vec![63]);
+        let hit = physical_attack(&mut rng, &player, &metal, ACCURACY, CRITICAL);
+        assert_eq!(
+            hit,
+            Hit {
+                damage: 0,
+                critical: false,
+                missed: true
+            }
+        );
+        assert_eq!(rng.consumed(), 1);
+
+        // A monster does not draw for a critical hit, nor a target without
+        // evasion for a dodge.
+        let mut rng = SequenceRng::new(vec![0]);
+        let hit = physical_attack(&mut rng, &metal, &player, ACCURACY, CRITICAL);
+        assert!(!hit.critical);
+        assert_eq!(rng.consumed(), 1);
+
+        let mut rng = SequenceRng::new(vec![0]);
+        assert_eq!(CRITICAL.damage.roll(&mut rng, 100), 94);
+    }
+
+    #[test]
+    fn test_heavy_blow() {
+        let hero = CombatantInit {
+            side: Character::Player,
+            max_hp: 100,
+            defense: 200,
+            ..Default::default()
+        }
+        .init();
+        let rabbit = CombatantInit {
+            attack: 40,
+            ..Default::default()
+        }
+        .init();
+        let mut battle = Battle::with_parties(Rng::default(), vec![hero], vec![rabbit]);
+
+        let mut rng = Rng::default();
+        let expected = CRITICAL.damage.roll(&mut rng, 40);
+        let hit = battle.heavy_blow(
+            CombatantId::monster(0),
+            CombatantId::player(0),
+            CRITICAL.damage,
+        );
+
+        assert!(hit.critical);
+        assert_eq!(hit.damage, expected);
+        assert_eq!(battle.party()[0].hp, 100 - expected as u16);
+        assert_eq!(battle.rng().state(), rng.state());
+    }
+
+    #[test]
+    fn test_turn_order() {
+        // A made-up scaling to test the order, not the one of the game.
+        let order_value = |agility: u16, rand: u8| agility as u32 * rand as u32;
+
+        let combatant = |side, agility| {
+            CombatantInit {
+                side,
+                max_hp: 10,
+                agility,
+                ..Default::default()
+            }
+            .init()
+        };
+        let party = vec![
+            combatant(Character::Player, 30),
+            combatant(Character::Player, 5),
+        ];
+        let mut monsters = vec![
+            combatant(Character::Monster, 20),
+            combatant(Character::Monster, 12),
+        ];
+        monsters[1].hp = 0;
+        let mut battle = Battle::with_parties(Rng::default(), party, monsters);
+
+        let order = battle.turn_order(order_value);
+        let mut rng = Rng::default();
+        let expected = order::roll_order(
+            &mut rng,
+            &[
+                (CombatantId::player(0), 30),
+                (CombatantId::player(1), 5),
+                (CombatantId::monster(0), 20),
+            ],
+            order_value,
+        );
+        assert_eq!(order, expected);
+        assert_eq!(battle.rng().state(), State::default().advance(48));
+
+        let (player, monster, dead) = (
+            CombatantId::player(0),
+            CombatantId::monster(0),
+            CombatantId::monster(1),
+        );
+        let p = battle.probability_acts_before(player, monster, order_value);
+        assert_eq!(p, order::probability_acts_before(30, 20, true, order_value));
+        let p = battle.probability_acts_before(monster, player, order_value);
+        assert_eq!(
+            p,
+            order::probability_acts_before(20, 30, false, order_value)
+        );
+        assert_eq!(
+            battle.probability_acts_before(player, dead, order_value),
+            0.0
+        );
+        assert_eq!(
+            battle.probability_acts_before(dead, player, order_value),
+            0.0
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_probability_acts_before_itself() {
+        let combatant = CombatantInit {
+            side: Character::Player,
+            ..Default::default()
+        }
+        .init();
+        let battle = Battle::with_parties(Rng::default(), vec![combatant], vec![]);
+        battle.probability_acts_before(CombatantId::player(0), CombatantId::player(0), |_, r| {
+            r as u32
+        });
+    }
 }
@@ -0,0 +1,151 @@
+use crate::battle::combatant::{Combatant, Status};
+use crate::rand::RandomSource;
+
+/// How a physical attack hits, which is yet to be taken from the ROM, so the
+/// caller provides it. The evasion of each target is `Combatant::evasion`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Accuracy {
+    /// The `rand()` threshold for a dazzled (マヌーサ) attacker to hit, i.e. in
+    /// 1/256.
+    pub dazzled_hit: u8,
+    /// Whether a target of no evasion skips the draw for a dodge, since it
+    /// never dodges anyway.
+    pub skip_no_evasion: bool,
+}
+
+/// Roll whether a physical attack by `attacker` hits `target`.
+///
+/// A dazzled attacker first draws one `rand()`, and misses unless it is below
+/// `accuracy.dazzled_hit`. Then a target which can dodge draws one `rand()`,
+/// and dodges if it is below the evasion. Nothing more is drawn on a miss, and
+/// nothing is drawn for a target which cannot dodge.
+pub fn roll_hit<R: RandomSource>(
+    rng: &mut R,
+    attacker: &Combatant,
+    target: &Combatant,
+    accuracy: Accuracy,
+) -> bool {
+    if attacker.has_status(Status::Dazzled) {
+        rng.set_tag(format_args!("dazzled"));
+        if rng.rand() >= accuracy.dazzled_hit {
+            return false;
+        }
+    }
+
+    if !target.can_dodge() || (target.evasion == 0 && accuracy.skip_no_evasion) {
+        return true;
+    }
+
+    rng.set_tag(format_args!("dodge"));
+    rng.rand() >= target.evasion
+}
+
+/// Return the exact probability that a physical attack by `attacker` hits
+/// `target`, see `roll_hit`.
+pub fn hit_probability(attacker: &Combatant, target: &Combatant, accuracy: Accuracy) -> f64 {
+    let hit = if attacker.has_status(Status::Dazzled) {
+        accuracy.dazzled_hit as f64 / 256.0
+    } else {
+        1.0
+    };
+    if !target.can_dodge() {
+        return hit;
+    }
+    hit * (1.0 - target.evasion as f64 / 256.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::battle::combatant::CombatantInit;
+    use crate::battle::Character;
+    use crate::rand::{pmf, SequenceRng, State};
+    use crate::testing::assert_close;
+
+    // A made-up rule to test the accuracy, not the one of the game.
+    const ACCURACY: Accuracy = Accuracy {
+        dazzled_hit: 64,
+        skip_no_evasion: true,
+    };
+
+    fn combatants() -> (Combatant, Combatant) {
+        let hero = CombatantInit {
+            side: Character::Player,
+            ..Default::default()
+        }
+        .init();
+        let raven = CombatantInit {
+            evasion: 8,
+            ..Default::default()
+        }
+        .init();
+        (hero, raven)
+    }
+
+    #[test]
+    fn test_roll_hit() {
+        let (hero, mut raven) = combatants();
+
+        let mut rng = SequenceRng::new(vec![7]);
+        assert!(!roll_hit(&mut rng, &hero, &raven, ACCURACY));
+        let mut rng = SequenceRng::new(vec![8]);
+        assert!(roll_hit(&mut rng, &hero, &raven, ACCURACY));
+
+        // A target of no evasion draws only if the caller asks to.
+        let mut rng = SequenceRng::new(vec![0]);
+        assert!(roll_hit(&mut rng, &raven, &hero, ACCURACY));
+        assert_eq!(rng.consumed(), 0);
+        let draw = Accuracy {
+            skip_no_evasion: false,
+            ..ACCURACY
+        };
+        assert!(roll_hit(&mut rng, &raven, &hero, draw));
+        assert_eq!(rng.consumed(), 1);
+
+        // A sleeping target never dodges nor draws.
+        raven.statuses[Status::Asleep] = true;
+        let mut rng = SequenceRng::new(vec![]);
+        assert!(roll_hit(&mut rng, &hero, &raven, ACCURACY));
+    }
+
+    #[test]
+    fn test_roll_hit_dazzled() {
+        let (mut hero, raven) = combatants();
+        hero.statuses[Status::Dazzled] = true;
+
+        // A miss by the dazzle draws nothing for a dodge.
+        let mut rng = SequenceRng::new(vec![64, 255]);
+        assert!(!roll_hit(&mut rng, &hero, &raven, ACCURACY));
+        assert_eq!(rng.consumed(), 1);
+
+        let mut rng = SequenceRng::new(vec![63, 255]);
+        assert!(roll_hit(&mut rng, &hero, &raven, ACCURACY));
+        assert_eq!(rng.consumed(), 2);
+
+        let mut rng = SequenceRng::new(vec![63, 7]);
+        assert!(!roll_hit(&mut rng, &hero, &raven, ACCURACY));
+    }
+
+    #[test]
+    fn test_hit_probability() {
+        let (mut hero, mut raven) = combatants();
+
+        assert_eq!(hit_probability(&hero, &raven, ACCURACY), 248.0 / 256.0);
+        assert_eq!(hit_probability(&raven, &hero, ACCURACY), 1.0);
+
+        hero.statuses[Status::Dazzled] = true;
+        assert_eq!(
+            hit_probability(&hero, &raven, ACCURACY),
+            64.0 / 256.0 * 248.0 / 256.0
+        );
+
+        // Compare with rolls from successive states.
+        let frequency = pmf::empirical(State::default(), 20000, |rng| {
+            roll_hit(rng, &hero, &raven, ACCURACY) as u8
+        })[1];
+        assert_close(frequency, hit_probability(&hero, &raven, ACCURACY), 0.02);
+
+        raven.statuses[Status::Paralyzed] = true;
+        assert_eq!(hit_probability(&hero, &raven, ACCURACY), 64.0 / 256.0);
+    }
+}
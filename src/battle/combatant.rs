@@ -0,0 +1,259 @@
+use crate::attr::Attr;
+use crate::battle::Character;
+use crate::job::Job;
+use crate::player::Player;
+
+use enum_iterator::IntoEnumIterator;
+use enum_map::{Enum, EnumMap};
+use serde::{Deserialize, Serialize};
+
+/// Kind of spells and breaths which a combatant may resist.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Deserialize,
+    Display,
+    Enum,
+    EnumString,
+    IntoEnumIterator,
+    PartialEq,
+    Eq,
+    Serialize,
+)]
+pub enum Element {
+    #[strum(serialize = "メラ")]
+    Frizz,
+    #[strum(serialize = "ギラ")]
+    Sizz,
+    #[strum(serialize = "ヒャド")]
+    Crack,
+    #[strum(serialize = "イオ")]
+    Bang,
+    #[strum(serialize = "バギ")]
+    Woosh,
+    #[strum(serialize = "デイン")]
+    Zap,
+    #[strum(serialize = "ザキ")]
+    Whack,
+    #[strum(serialize = "ラリホー")]
+    Snooze,
+    #[strum(serialize = "マヌーサ")]
+    Dazzle,
+    #[strum(serialize = "マホトーン")]
+    Fizzle,
+    #[strum(serialize = "ニフラム")]
+    Expel,
+    #[strum(serialize = "ほのお")]
+    FireBreath,
+    #[strum(serialize = "ふぶき")]
+    IceBreath,
+}
+
+/// The resistance level to each element, from 0 (none) to 3 (immune).
+pub type Resistances = EnumMap<Element, u8>;
+
+/// Status ailments.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Deserialize,
+    Display,
+    Enum,
+    EnumString,
+    IntoEnumIterator,
+    PartialEq,
+    Eq,
+    Serialize,
+)]
+pub enum Status {
+    #[strum(serialize = "ねむり")]
+    Asleep,
+    #[strum(serialize = "まひ")]
+    Paralyzed,
+    #[strum(serialize = "こんらん")]
+    Confused,
+    #[strum(serialize = "マホトーン")]
+    Fizzled,
+    /// Blinded by Dazzle (マヌーサ), also known as Surround.
+    #[strum(serialize = "マヌーサ")]
+    Dazzled,
+    #[strum(serialize = "どく")]
+    Poisoned,
+}
+
+/// A participant of a battle, either a player or a monster.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Combatant {
+    pub name: String,
+    pub side: Character,
+    /// The job of a player, or `None` for a monster.
+    pub job: Option<Job>,
+    pub level: u8,
+    pub max_hp: u16,
+    pub hp: u16,
+    pub max_mp: u16,
+    pub mp: u16,
+    pub attack: i16,
+    pub defense: i16,
+    pub agility: u16,
+    /// The `rand()` threshold to dodge a physical attack, i.e. in 1/256.
+    pub evasion: u8,
+    pub resistances: Resistances,
+    pub statuses: EnumMap<Status, bool>,
+}
+
+impl Combatant {
+    pub fn is_alive(&self) -> bool {
+        self.hp > 0
+    }
+
+    pub fn has_status(&self, status: Status) -> bool {
+        self.statuses[status]
+    }
+
+    /// Return whether it can dodge an attack, i.e. neither asleep nor paralyzed.
+    pub fn can_dodge(&self) -> bool {
+        !self.has_status(Status::Asleep) && !self.has_status(Status::Paralyzed)
+    }
+
+    /// Reduce HP by `damage` down to 0, and return the damage actually taken.
+    pub fn take_damage(&mut self, damage: u16) -> u16 {
+        let taken = u16::min(self.hp, damage);
+        self.hp -= taken;
+        taken
+    }
+
+    /// Restore HP by `amount` up to the max, unless dead.
+    pub fn heal(&mut self, amount: u16) {
+        if self.is_alive() {
+            self.hp = u16::min(self.max_hp, self.hp.saturating_add(amount));
+        }
+    }
+}
+
+/// Build a player without equipment: the attack is Pow and the defense is
+/// half of Spd, both in full HP and MP. The evasion is 0, see
+/// `accuracy::roll_hit`.
+impl From<&Player> for Combatant {
+    fn from(player: &Player) -> Self {
+        CombatantInit {
+            name: player.job().to_string(),
+            side: Character::Player,
+            job: Some(player.job()),
+            level: player.level(),
+            max_hp: player.max_hp,
+            max_mp: player.max_mp,
+            attack: player.attr(Attr::Pow) as i16,
+            defense: (player.attr(Attr::Spd) / 2) as i16,
+            agility: player.attr(Attr::Spd) as u16,
+            ..Default::default()
+        }
+        .init()
+    }
+}
+
+#[derive(Debug)]
+pub struct CombatantInit {
+    pub name: String,
+    pub side: Character,
+    pub job: Option<Job>,
+    pub level: u8,
+    pub max_hp: u16,
+    pub max_mp: u16,
+    pub attack: i16,
+    pub defense: i16,
+    pub agility: u16,
+    pub evasion: u8,
+    pub resistances: Resistances,
+}
+
+impl Default for CombatantInit {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            side: Character::Monster,
+            job: None,
+            level: 1,
+            max_hp: 1,
+            max_mp: 0,
+            attack: 0,
+            defense: 0,
+            agility: 0,
+            evasion: 0,
+            resistances: Resistances::default(),
+        }
+    }
+}
+
+impl CombatantInit {
+    /// Build a combatant in full HP and MP without any status.
+    pub fn init(self) -> Combatant {
+        Combatant {
+            name: self.name,
+            side: self.side,
+            job: self.job,
+            level: self.level,
+            max_hp: self.max_hp,
+            hp: self.max_hp,
+            max_mp: self.max_mp,
+            mp: self.max_mp,
+            attack: self.attack,
+            defense: self.defense,
+            agility: self.agility,
+            evasion: self.evasion,
+            resistances: self.resistances,
+            statuses: EnumMap::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::PlayerInit;
+
+    #[test]
+    fn test_from_player() {
+        let player = PlayerInit {
+            lv: 5,
+            max_hp: 40,
+            max_mp: 10,
+            pow: 20,
+            spd: 15,
+            job: Job::Soldier,
+            ..Default::default()
+        }
+        .init();
+
+        let combatant = Combatant::from(&player);
+        assert_eq!(combatant.side, Character::Player);
+        assert_eq!(combatant.job, Some(Job::Soldier));
+        assert_eq!(combatant.level, 5);
+        assert_eq!(combatant.hp, 40);
+        assert_eq!(combatant.mp, 10);
+        assert_eq!(combatant.attack, 20);
+        assert_eq!(combatant.defense, 7);
+        assert_eq!(combatant.agility, 15);
+        assert_eq!(combatant.evasion, 0);
+        assert!(!combatant.has_status(Status::Asleep));
+    }
+
+    #[test]
+    fn test_damage_and_heal() {
+        let mut combatant = CombatantInit {
+            max_hp: 30,
+            ..Default::default()
+        }
+        .init();
+
+        assert_eq!(combatant.take_damage(10), 10);
+        combatant.heal(100);
+        assert_eq!(combatant.hp, 30);
+        assert_eq!(combatant.take_damage(50), 30);
+        assert!(!combatant.is_alive());
+        combatant.heal(10);
+        assert_eq!(combatant.hp, 0);
+    }
+}
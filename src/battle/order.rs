@@ -0,0 +1,181 @@
+use crate::battle::CombatantId;
+use crate::rand::{pmf, RandomSource};
+
+use std::cmp::Reverse;
+
+/// The offset of `rand_multinomial` for the order of actors, see §2.2 of
+/// jupyter/RNG.ipynb.
+pub const ORDER_OFFSET: u8 = 136;
+
+/// The mask of `rand_multinomial` for the order of actors, see §2.2 of
+/// jupyter/RNG.ipynb.
+pub const ORDER_MASK: u8 = 0x1f;
+
+/// Roll the order of the actors in a round.
+///
+/// One `rand_multinomial(ORDER_OFFSET, ORDER_MASK)` is drawn for each actor in
+/// the given order. The actors are sorted by `order_value(agility, rand)` in
+/// descending order, and ties keep the given order.
+///
+/// How the game scales the agility by the random value is yet to be taken from
+/// the ROM, so the caller provides `order_value`.
+pub fn roll_order<R, F>(
+    rng: &mut R,
+    actors: &[(CombatantId, u16)],
+    order_value: F,
+) -> Vec<CombatantId>
+where
+    R: RandomSource,
+    F: Fn(u16, u8) -> u32,
+{
+    let mut values: Vec<(CombatantId, u32)> = actors
+        .iter()
+        .map(|(id, agility)| {
+            rng.set_tag(format_args!("order"));
+            let rand = rng.rand_multinomial(ORDER_OFFSET, ORDER_MASK);
+            (*id, order_value(*agility, rand))
+        })
+        .collect();
+    values.sort_by_key(|(_, value)| Reverse(*value));
+    values.into_iter().map(|(id, _)| id).collect()
+}
+
+/// Return the exact probability that an actor of `agility` acts before one of
+/// `other_agility`, see `roll_order`.
+///
+/// The two random values are assumed to be independent, each distributed as
+/// `pmf::rand_multinomial(ORDER_OFFSET, ORDER_MASK)`. `first_on_tie` is whether
+/// the actor comes first in the draw order, which decides the order on a tie.
+pub fn probability_acts_before<F>(
+    agility: u16,
+    other_agility: u16,
+    first_on_tie: bool,
+    order_value: F,
+) -> f64
+where
+    F: Fn(u16, u8) -> u32,
+{
+    let pmf = pmf::rand_multinomial(ORDER_OFFSET, ORDER_MASK);
+    let mut probability = 0.0;
+    for r in 0..=255 {
+        let value = order_value(agility, r);
+        for s in 0..=255 {
+            let other = order_value(other_agility, s);
+            if value > other || (value == other && first_on_tie) {
+                probability += pmf[r as usize] * pmf[s as usize];
+            }
+        }
+    }
+    probability
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rand::{Rng, SequenceRng, State};
+    use crate::testing::assert_close;
+
+    use std::cell::RefCell;
+
+    // A made-up scaling to test the order, not the one of the game.
+    fn order_value(agility: u16, rand: u8) -> u32 {
+        agility as u32 * rand as u32
+    }
+
+    // 16 values of `rand()` which make `rand_multinomial(136, 0x1f)` return
+    // `136 + 16 * (byte & 0x1f)` wrapped around.
+    fn multinomial(bytes: &[u8]) -> Vec<u8> {
+        bytes.iter().flat_map(|b| vec![*b; 16]).collect()
+    }
+
+    #[test]
+    fn test_order_rand() {
+        let actors = [(CombatantId::player(0), 100), (CombatantId::monster(0), 60)];
+        let drawn = RefCell::new(Vec::new());
+
+        let mut rng = SequenceRng::new(multinomial(&[1, 31]));
+        roll_order(&mut rng, &actors, |agility, rand| {
+            drawn.borrow_mut().push((agility, rand));
+            0
+        });
+
+        assert_eq!(drawn.into_inner(), vec![(100, 152), (60, 120)]);
+        assert_eq!(rng.consumed(), 32);
+    }
+
+    #[test]
+    fn test_roll_order() {
+        let actors = [
+            (CombatantId::player(0), 100),
+            (CombatantId::player(1), 40),
+            (CombatantId::monster(0), 60),
+        ];
+
+        // The values are 100 * 136, 40 * 200 and 60 * 120.
+        let mut rng = SequenceRng::new(multinomial(&[0, 4, 31]));
+        assert_eq!(
+            roll_order(&mut rng, &actors, order_value),
+            vec![
+                CombatantId::player(0),
+                CombatantId::player(1),
+                CombatantId::monster(0)
+            ]
+        );
+
+        // The values are 100 * 120, 40 * 136 and 60 * 248.
+        let mut rng = SequenceRng::new(multinomial(&[31, 0, 7]));
+        assert_eq!(
+            roll_order(&mut rng, &actors, order_value),
+            vec![
+                CombatantId::monster(0),
+                CombatantId::player(0),
+                CombatantId::player(1)
+            ]
+        );
+
+        // A tie keeps the given order.
+        let actors = [
+            (CombatantId::monster(0), 100),
+            (CombatantId::player(0), 100),
+        ];
+        let mut rng = SequenceRng::new(multinomial(&[3, 3]));
+        assert_eq!(
+            roll_order(&mut rng, &actors, order_value),
+            vec![CombatantId::monster(0), CombatantId::player(0)]
+        );
+
+        // Each actor takes 16 transitions.
+        let mut rng = Rng::default();
+        roll_order(&mut rng, &actors, order_value);
+        assert_eq!(rng.state(), State::default().advance(32));
+    }
+
+    #[test]
+    fn test_probability_acts_before() {
+        assert_close(probability_acts_before(0, 0, true, order_value), 1.0, 1e-12);
+        assert_eq!(probability_acts_before(0, 0, false, order_value), 0.0);
+
+        // The difference is the probability of a tie.
+        let p = probability_acts_before(30, 30, true, order_value);
+        let q = probability_acts_before(30, 30, false, order_value);
+        assert!(p - q > 0.0);
+        assert_close(p + q, 1.0, 1e-12);
+        assert_close(
+            probability_acts_before(30, 25, true, order_value)
+                + probability_acts_before(25, 30, false, order_value),
+            1.0,
+            1e-12,
+        );
+
+        // Compare with rolls from successive states.
+        let actors = [(CombatantId::player(0), 30), (CombatantId::monster(0), 25)];
+        let frequency = pmf::empirical(State::default(), 20000, |rng| {
+            (roll_order(rng, &actors, order_value)[0] == CombatantId::player(0)) as u8
+        })[1];
+        assert_close(
+            frequency,
+            probability_acts_before(30, 25, true, order_value),
+            0.02,
+        );
+    }
+}
@@ -0,0 +1,146 @@
+use crate::attr::Attr;
+use crate::personality::Personality;
+use crate::player::{self, Player};
+use crate::rand::frame::FrameTimeline;
+use crate::rand::{Rng, State};
+use crate::sex::Sex;
+
+use enum_iterator::IntoEnumIterator;
+use enum_map::EnumMap;
+use std::ops::RangeInclusive;
+
+/// The minimum stats of a hero to aim for.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeroTarget {
+    pub attrs: EnumMap<Attr, u8>,
+    pub max_hp: u16,
+    pub max_mp: u16,
+}
+
+impl HeroTarget {
+    pub fn is_met(&self, player: &Player) -> bool {
+        Attr::into_enum_iter().all(|attr| player.attr(attr) >= self.attrs[attr])
+            && player.max_hp >= self.max_hp
+            && player.max_mp >= self.max_mp
+    }
+}
+
+/// A hero obtained by confirming the creation after waiting `frame` frames.
+#[derive(Debug, Clone)]
+pub struct HeroCandidate {
+    pub frame: u64,
+    pub player: Player,
+}
+
+/// A finder of the frame to confirm the hero creation at.
+///
+/// The RNG keeps transiting every frame until the name/personality is
+/// confirmed, when `player::init_attr_of_hero` draws the attributes. So the
+/// stats depend only on the number of frames waited from a known state.
+#[derive(Debug, Clone)]
+pub struct HeroFinder {
+    state: State,
+    frames: RangeInclusive<u64>,
+    sex: Sex,
+    personality: Personality,
+}
+
+impl HeroFinder {
+    /// Create a finder for confirming after waiting a number of frames in
+    /// `frames` from `state`.
+    pub fn new(
+        state: State,
+        frames: RangeInclusive<u64>,
+        sex: Sex,
+        personality: Personality,
+    ) -> Self {
+        Self {
+            state,
+            frames,
+            sex,
+            personality,
+        }
+    }
+
+    /// Return the hero at each frame in the window, in ascending order of frame.
+    pub fn candidates(&self) -> Vec<HeroCandidate> {
+        FrameTimeline::new(Rng::from(self.state))
+            .scan_delays(self.frames.clone(), |rng| {
+                player::init_attr_of_hero_with(self.sex, self.personality, rng)
+            })
+            .into_iter()
+            .map(|(frame, player)| HeroCandidate { frame, player })
+            .collect()
+    }
+
+    /// Return the earliest hero in the window meeting `target`.
+    pub fn find(&self, target: &HeroTarget) -> Option<HeroCandidate> {
+        self.find_by(|player| target.is_met(player))
+    }
+
+    /// Return the earliest hero in the window for which `predicate` returns true.
+    pub fn find_by<F: Fn(&Player) -> bool>(&self, predicate: F) -> Option<HeroCandidate> {
+        let mut timeline = FrameTimeline::new(Rng::from(self.state));
+        timeline.wait(*self.frames.start());
+
+        for frame in self.frames.clone() {
+            let player = timeline
+                .clone()
+                .act(|rng| player::init_attr_of_hero_with(self.sex, self.personality, rng));
+            if predicate(&player) {
+                return Some(HeroCandidate { frame, player });
+            }
+            timeline.wait(1);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidates() {
+        let state = State::new(0x12345678);
+        let finder = HeroFinder::new(state, 10..=20, Sex::Man, Personality::Tough);
+
+        let candidates = finder.candidates();
+        assert_eq!(candidates.len(), 11);
+        for candidate in candidates.iter() {
+            let mut rng = Rng::from(state);
+            rng.advance_frames(candidate.frame);
+            let expected = player::init_attr_of_hero_with(Sex::Man, Personality::Tough, &mut rng);
+
+            assert_eq!(candidate.player.attrs, expected.attrs);
+            assert_eq!(candidate.player.max_hp, expected.max_hp);
+            assert_eq!(candidate.player.max_mp, expected.max_mp);
+        }
+    }
+
+    #[test]
+    fn test_find() {
+        let finder = HeroFinder::new(State::default(), 0..=300, Sex::Women, Personality::Tough);
+        let candidates = finder.candidates();
+
+        // Aim at the best Vit in the window.
+        let best_vit = candidates
+            .iter()
+            .map(|c| c.player.attr(Attr::Vit))
+            .max()
+            .unwrap();
+        let mut target = HeroTarget::default();
+        target.attrs[Attr::Vit] = best_vit;
+
+        let found = finder.find(&target).unwrap();
+        let expected = candidates
+            .iter()
+            .find(|c| c.player.attr(Attr::Vit) >= best_vit)
+            .unwrap();
+        assert_eq!(found.frame, expected.frame);
+        assert_eq!(found.player.attrs, expected.player.attrs);
+
+        target.max_hp = u16::MAX;
+        assert!(finder.find(&target).is_none());
+    }
+}
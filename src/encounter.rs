@@ -0,0 +1,157 @@
+use crate::rand::{self, RandomSource};
+
+pub mod route;
+
+/// The number of `rand_by_multiply(16)` values summed up for the initial counter.
+pub const INITIAL_DRAWS: usize = 3;
+
+/// The maximum initial counter.
+pub const INITIAL_MAX: u8 = 16 * INITIAL_DRAWS as u8;
+
+/// Return an initial counter, i.e. the sum of 3 values of `rand_by_multiply(16)`.
+pub fn initial_counter<R: RandomSource>(rng: &mut R) -> u8 {
+    (0..INITIAL_DRAWS).map(|_| rng.rand_by_multiply(16)).sum()
+}
+
+/// The encounter counter, which decreases every step and triggers a battle at 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncounterCounter {
+    value: u8,
+}
+
+impl EncounterCounter {
+    pub fn new<R: RandomSource>(rng: &mut R) -> Self {
+        Self {
+            value: initial_counter(rng),
+        }
+    }
+
+    pub fn from_value(value: u8) -> Self {
+        Self { value }
+    }
+
+    pub fn value(&self) -> u8 {
+        self.value
+    }
+
+    /// Take a step decreasing the counter by `decrement`, and return whether a
+    /// monster appears.
+    ///
+    /// The decrement depends on the terrain of the tile, and the values per
+    /// terrain are yet to be taken from the ROM, so the caller gives it. A
+    /// decrement of 0 stands for a tile where no monster appears. The counter
+    /// stays at 0 after an encounter until `reset`.
+    pub fn step(&mut self, decrement: u8) -> bool {
+        if decrement == 0 {
+            return false;
+        }
+
+        self.value = self.value.saturating_sub(decrement);
+        self.value == 0
+    }
+
+    /// Reinitialize the counter after a battle.
+    pub fn reset<R: RandomSource>(&mut self, rng: &mut R) {
+        self.value = initial_counter(rng);
+    }
+}
+
+/// Return the probability mass function of the initial counter, indexed by the
+/// value in [0, INITIAL_MAX], assuming `rand()` values are uniform and independent.
+pub fn initial_counter_pmf() -> Vec<f64> {
+    let draw = rand::pmf::rand_by_multiply(16);
+
+    let mut pmf = vec![1.0];
+    for _ in 0..INITIAL_DRAWS {
+        let mut next = vec![0.0; pmf.len() + draw.len() - 1];
+        for (s, p) in pmf.iter().enumerate() {
+            for (v, q) in draw.iter().enumerate() {
+                next[s + v] += p * q;
+            }
+        }
+        pmf = next;
+    }
+    pmf
+}
+
+/// Return the probability that a monster appears within `steps` steps, each
+/// decreasing the counter by `decrement`, from a freshly initialized counter.
+pub fn probability_within(decrement: u8, steps: u32) -> f64 {
+    let reach = steps as u64 * decrement as u64;
+    if steps == 0 || reach == 0 {
+        return 0.0;
+    }
+
+    initial_counter_pmf()
+        .iter()
+        .take(usize::min(reach as usize, INITIAL_MAX as usize) + 1)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rand::{Rng, SequenceRng};
+    use crate::testing::assert_close;
+
+    #[test]
+    fn test_initial_counter() {
+        // rand_by_multiply(16) of 255, 128 and 0 is 16, 8 and 0.
+        let mut rng = SequenceRng::new(vec![255, 128, 0]);
+        assert_eq!(initial_counter(&mut rng), 24);
+
+        let mut rng = Rng::default();
+        let mut expected = Rng::default();
+        let value = initial_counter(&mut rng);
+        assert_eq!(
+            value,
+            (0..3).map(|_| expected.rand_by_multiply(16)).sum::<u8>()
+        );
+        assert!(value <= INITIAL_MAX);
+    }
+
+    #[test]
+    fn test_step_and_reset() {
+        let mut counter = EncounterCounter::from_value(7);
+
+        assert!(!counter.step(2));
+        assert!(!counter.step(0));
+        assert_eq!(counter.value(), 5);
+        assert!(!counter.step(3));
+        assert!(counter.step(4));
+        assert_eq!(counter.value(), 0);
+        assert!(counter.step(2));
+
+        counter.reset(&mut SequenceRng::new(vec![255, 255, 255]));
+        assert_eq!(counter.value(), INITIAL_MAX);
+    }
+
+    #[test]
+    fn test_initial_counter_pmf() {
+        let pmf = initial_counter_pmf();
+
+        assert_eq!(pmf.len(), INITIAL_MAX as usize + 1);
+        assert_close(pmf.iter().sum(), 1.0, 1e-12);
+        // rand() in [241, 255] gives 16 in rand_by_multiply(16).
+        assert_close(pmf[INITIAL_MAX as usize], (15.0 / 256.0_f64).powi(3), 1e-15);
+    }
+
+    #[test]
+    fn test_probability_within() {
+        assert_eq!(probability_within(2, 0), 0.0);
+        assert_eq!(probability_within(0, 100), 0.0);
+        assert_close(probability_within(2, 24), 1.0, 1e-12);
+        assert_close(probability_within(4, 12), 1.0, 1e-12);
+
+        // The counter is 0 to 2 in 1 step of 2.
+        let pmf = initial_counter_pmf();
+        assert_close(probability_within(2, 1), pmf[0] + pmf[1] + pmf[2], 1e-12);
+
+        // Simulate from successive states.
+        let frequency = rand::pmf::empirical(rand::State::default(), 100000, |rng| {
+            let mut counter = EncounterCounter::new(rng);
+            (0..8).any(|_| counter.step(3)) as u8
+        })[1];
+        assert_close(frequency, probability_within(3, 8), 0.01);
+    }
+}
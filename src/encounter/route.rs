@@ -0,0 +1,239 @@
+use crate::encounter::EncounterCounter;
+use crate::rand::frame::FrameTimeline;
+use crate::rand::{RandomSource, Rng, State};
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+
+/// A tile to step on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Tile {
+    /// The decrement of the counter on this tile, see `EncounterCounter::step`.
+    pub decrement: u8,
+    /// The ID of the area, which decides the monster groups.
+    pub area: u16,
+}
+
+impl Tile {
+    pub fn new(decrement: u8, area: u16) -> Self {
+        Self { decrement, area }
+    }
+}
+
+/// The encounters on a walk along a route.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Walk {
+    /// The indices of the tiles where monsters appear, in ascending order.
+    pub positions: Vec<usize>,
+}
+
+impl Walk {
+    pub fn count(&self) -> usize {
+        self.positions.len()
+    }
+}
+
+/// Walk along `route` from a freshly initialized counter, drawing random
+/// numbers from `rng`.
+///
+/// The counter is reset right after each encounter. The random numbers the
+/// battles themselves consume are not modeled, so split the route at the
+/// encounters to take them into account.
+pub fn walk<R: RandomSource>(route: &[Tile], rng: &mut R) -> Walk {
+    let mut counter = EncounterCounter::new(rng);
+    let mut positions = vec![];
+    for (i, tile) in route.iter().enumerate() {
+        if counter.step(tile.decrement) {
+            positions.push(i);
+            counter.reset(rng);
+        }
+    }
+    Walk { positions }
+}
+
+/// The distribution of encounters on a route over many walks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteDistribution {
+    pub walks: usize,
+    /// `counts[k]` is the probability of exactly `k` encounters.
+    pub counts: Vec<f64>,
+    /// `positions[i]` is the probability of an encounter on the `i`-th tile.
+    pub positions: Vec<f64>,
+    /// The expected number of encounters in each area.
+    pub areas: BTreeMap<u16, f64>,
+}
+
+impl RouteDistribution {
+    /// Aggregate the walks along `route`.
+    ///
+    /// Panics if there is no walk.
+    pub fn from_walks<I: IntoIterator<Item = Walk>>(route: &[Tile], walks: I) -> Self {
+        let mut n = 0;
+        let mut counts = vec![0_u64];
+        let mut positions = vec![0_u64; route.len()];
+        for walk in walks {
+            n += 1;
+            if counts.len() <= walk.count() {
+                counts.resize(walk.count() + 1, 0);
+            }
+            counts[walk.count()] += 1;
+            for i in walk.positions {
+                positions[i] += 1;
+            }
+        }
+        assert!(n > 0, "no walk to aggregate");
+
+        let to_probability = |c: &u64| *c as f64 / n as f64;
+        let positions: Vec<f64> = positions.iter().map(to_probability).collect();
+        let mut areas = BTreeMap::new();
+        for (tile, p) in route.iter().zip(positions.iter()) {
+            *areas.entry(tile.area).or_insert(0.0) += p;
+        }
+
+        Self {
+            walks: n,
+            counts: counts.iter().map(to_probability).collect(),
+            positions,
+            areas,
+        }
+    }
+
+    /// Return the expected number of encounters.
+    pub fn mean(&self) -> f64 {
+        self.positions.iter().sum()
+    }
+
+    /// Return the probability of no encounter.
+    pub fn no_encounter(&self) -> f64 {
+        self.counts[0]
+    }
+}
+
+/// Return the distribution of encounters when starting to walk along `route`
+/// after waiting each number of frames in `delays` from `state`.
+pub fn distribution_over_delays(
+    route: &[Tile],
+    state: State,
+    delays: RangeInclusive<u64>,
+) -> RouteDistribution {
+    let walks = FrameTimeline::new(Rng::from(state))
+        .scan_delays(delays, |rng| walk(route, rng))
+        .into_iter()
+        .map(|(_, walk)| walk);
+    RouteDistribution::from_walks(route, walks)
+}
+
+/// Return the distribution of encounters when starting to walk along `route`
+/// from each of `states`.
+pub fn distribution_over_states<I: IntoIterator<Item = State>>(
+    route: &[Tile],
+    states: I,
+) -> RouteDistribution {
+    let walks = states
+        .into_iter()
+        .map(|state| walk(route, &mut Rng::from(state)));
+    RouteDistribution::from_walks(route, walks)
+}
+
+/// Return the distribution of encounters over `walks` uniformly random states
+/// drawn from `rng`, e.g. a seeded `rand::rngs::StdRng` to reproduce it.
+pub fn distribution_over_random_states<R: ::rand::Rng>(
+    route: &[Tile],
+    walks: usize,
+    rng: &mut R,
+) -> RouteDistribution {
+    distribution_over_states(route, (0..walks).map(|_| State::new(rng.gen())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rand::SequenceRng;
+    use crate::testing::assert_close;
+    use ::rand::rngs::StdRng;
+    use ::rand::SeedableRng;
+
+    fn route() -> Vec<Tile> {
+        let mut route = vec![Tile::new(0, 0); 3];
+        route.extend(vec![Tile::new(2, 1); 30]);
+        route.extend(vec![Tile::new(3, 2); 20]);
+        route
+    }
+
+    #[test]
+    fn test_walk() {
+        let route = vec![Tile::new(2, 1); 10];
+
+        // The counters are 8 + 0 + 0, 0 + 0 + 12 and 16 + 16 + 16.
+        let mut rng = SequenceRng::new(vec![128, 0, 0, 0, 0, 181, 255, 255, 255]);
+        let walk = walk(&route, &mut rng);
+
+        assert_eq!(walk.positions, vec![3, 9]);
+        assert_eq!(walk.count(), 2);
+        assert_eq!(rng.consumed(), 9);
+    }
+
+    #[test]
+    fn test_distribution_over_states() {
+        let route = route();
+        let states: Vec<State> = (0..4096_u32)
+            .map(|i| State::new(i.wrapping_mul(0x0010_0001)))
+            .collect();
+        let distribution = distribution_over_states(&route, states.clone());
+
+        assert_eq!(distribution.walks, 4096);
+        assert_close(distribution.counts.iter().sum(), 1.0, 1e-9);
+        assert_close(distribution.areas.values().sum(), distribution.mean(), 1e-9);
+        assert_eq!(distribution.areas[&0], 0.0);
+        assert!(distribution.mean() > 2.0 && distribution.mean() < 6.0);
+
+        let walk0 = walk(&route, &mut Rng::from(states[0]));
+        let single = distribution_over_states(&route, states[..1].to_vec());
+        assert_eq!(single.counts[walk0.count()], 1.0);
+        for i in walk0.positions {
+            assert_eq!(single.positions[i], 1.0);
+        }
+    }
+
+    #[test]
+    fn test_distribution_over_delays() {
+        let route = route();
+        let state = State::new(0x12345678);
+        let distribution = distribution_over_delays(&route, state, 0..=99);
+
+        assert_eq!(distribution.walks, 100);
+
+        let mut rng = Rng::from(state);
+        rng.advance_frames(99);
+        let last = walk(&route, &mut rng);
+        let expected = RouteDistribution::from_walks(&route, vec![last]);
+        let only_last = distribution_over_delays(&route, state, 99..=99);
+        assert_eq!(only_last, expected);
+    }
+
+    #[test]
+    fn test_distribution_over_random_states() {
+        let route = route();
+        let distribution =
+            distribution_over_random_states(&route, 1000, &mut StdRng::seed_from_u64(1));
+
+        assert_eq!(distribution.walks, 1000);
+        assert_close(distribution.counts.iter().sum(), 1.0, 1e-9);
+        assert_eq!(
+            distribution,
+            distribution_over_random_states(&route, 1000, &mut StdRng::seed_from_u64(1))
+        );
+
+        // The RNG of the game is a `rand::Rng` as well.
+        let by_game = distribution_over_random_states(&route, 1000, &mut Rng::default());
+        assert_eq!(by_game.walks, 1000);
+        assert_ne!(by_game, distribution);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_no_walk() {
+        RouteDistribution::from_walks(&route(), vec![]);
+    }
+}
@@ -1,6 +1,7 @@
 use crate::job::Job;
 use crate::personality::Personality;
 use crate::player::{Player, PlayerInit};
+use crate::rand::{self, Rng, State};
 use crate::sex::Sex;
 
 use serde::{Deserialize, Serialize};
@@ -38,6 +39,8 @@ struct GrowthConfigEntry {
 pub struct GrowthConfig {
     init: GrowthInitEntry,
     configs: Vec<GrowthConfigEntry>,
+    /// The starting state of the RNG, e.g. "0xaae21259". The thread-local RNG is used if absent.
+    state: Option<State>,
 }
 
 #[derive(Clone, Debug)]
@@ -45,6 +48,7 @@ pub struct PlayerGrowther {
     config: GrowthConfig,
     iter_i: usize,
     player: Player,
+    rng: Option<Rng>,
 }
 
 impl PlayerGrowther {
@@ -52,6 +56,7 @@ impl PlayerGrowther {
         let mut new_config = GrowthConfig {
             init: config.init.clone(),
             configs: vec![],
+            state: config.state,
         };
 
         let mut lv = 1;
@@ -104,9 +109,22 @@ impl PlayerGrowther {
             config: new_config,
             iter_i: 0,
             player: player,
+            rng: config.state.map(Rng::from),
         }
     }
 
+    /// Draw random numbers from a RNG starting at `state` instead of the
+    /// thread-local one, so that the growth is reproducible.
+    pub fn with_rng(mut self, state: State) -> Self {
+        self.rng = Some(Rng::from(state));
+        self
+    }
+
+    /// Return the current state of the RNG set by `with_rng`, if any.
+    pub fn state(&self) -> Option<State> {
+        self.rng.as_ref().map(|rng| rng.state())
+    }
+
     pub fn finalize(&mut self) -> Player {
         self.last().unwrap()
     }
@@ -130,7 +148,10 @@ impl std::iter::Iterator for PlayerGrowther {
             if self.player.job() != config.job.unwrap() {
                 self.player.job_change(config.job.unwrap());
             } else {
-                self.player.levelup();
+                match &mut self.rng {
+                    Some(rng) => self.player.levelup_with(rng),
+                    None => self.player.levelup_with(&mut rand::thread_rng()),
+                }
             }
         }
 
@@ -165,10 +186,14 @@ pub fn player_by_config(config_str: &str) -> Player {
     }
     .init();
 
+    let mut rng = config.state.map(Rng::from);
     for entry in &config.configs {
         let target_lv = entry.lv;
         for _ in player.level()..target_lv {
-            player.levelup();
+            match &mut rng {
+                Some(rng) => player.levelup_with(rng),
+                None => player.levelup(),
+            }
         }
 
         if let Some(job) = entry.job {
@@ -257,4 +282,67 @@ mod tests {
         let player = PlayerGrowther::from_config(&config).finalize();
         println!("{:?}", player);
     }
+
+    #[test]
+    fn test_with_rng() {
+        let config = r#"
+            {
+                "init": {
+                    "job": "Soldier",
+                    "sex": "Man",
+                    "personality": "Tough",
+                    "vit": 19
+                },
+                "configs": [
+                    {
+                        "lv": 22
+                    }
+                ]
+            }
+        "#;
+
+        let config: GrowthConfig = serde_json::from_str(config).unwrap();
+
+        let state = State::new(0x12345678);
+        let mut growther0 = PlayerGrowther::from_config(&config).with_rng(state);
+        let player0 = growther0.finalize();
+        rand::thread_rng().rand();
+        let mut growther1 = PlayerGrowther::from_config(&config).with_rng(state);
+        let player1 = growther1.finalize();
+
+        assert_eq!(player0.level(), 22);
+        assert_eq!(player0.attrs, player1.attrs);
+        assert_eq!(growther0.state(), growther1.state());
+        assert_ne!(growther0.state(), Some(state));
+    }
+
+    #[test]
+    fn test_state_in_config() {
+        let config = r#"
+            {
+                "init": {
+                    "job": "Soldier",
+                    "sex": "Man",
+                    "personality": "Tough",
+                    "vit": 19
+                },
+                "configs": [
+                    {
+                        "lv": 22
+                    }
+                ],
+                "state": "0x12345678"
+            }
+        "#;
+
+        let player = player_by_config(config);
+        let config: GrowthConfig = serde_json::from_str(config).unwrap();
+        let mut growther = PlayerGrowther::from_config(&config);
+        let expected = PlayerGrowther::from_config(&config)
+            .with_rng(State::new(0x12345678))
+            .finalize();
+
+        assert_eq!(growther.finalize().attrs, expected.attrs);
+        assert_eq!(player.attrs, expected.attrs);
+    }
 }
@@ -8,10 +8,15 @@ extern crate strum_macros;
 pub mod attr;
 pub mod battle;
 pub mod bit;
+pub mod creation;
+pub mod encounter;
 pub mod growth;
 pub mod job;
 mod loader;
+pub mod monster;
 pub mod personality;
 pub mod player;
 pub mod rand;
 pub mod sex;
+#[cfg(test)]
+mod testing;
@@ -1,4 +1,6 @@
 use csv;
+use std::fmt;
+use std::str::FromStr;
 
 pub trait FromRecord {
     fn from_record(record: &csv::StringRecord) -> Self;
@@ -14,3 +16,71 @@ pub fn from_csv<T: FromRecord>(data: &str) -> Vec<T> {
     }
     return vec;
 }
+
+/// An error in CSV provided by the caller.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    /// The line in the CSV, where the header is 1.
+    pub row: u64,
+    /// The column counted from 1, or 0 if the row itself is malformed.
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "row {}, column {}: {}",
+            self.row, self.column, self.message
+        )
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Like `FromRecord`, but for CSV provided by the caller.
+pub trait TryFromRecord: Sized {
+    fn try_from_record(record: &csv::StringRecord) -> Result<Self, Error>;
+}
+
+/// Like `from_csv`, but return the first error instead of panicking.
+///
+/// A row may have fewer columns than the header, in which case the error is on
+/// the first missing column.
+pub fn try_from_csv<T: TryFromRecord>(data: &str) -> Result<Vec<T>, Error> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(data.as_bytes());
+
+    let mut vec = Vec::new();
+    for result in rdr.records() {
+        let record = result.map_err(|e| Error {
+            row: e.position().map_or(0, |p| p.line()),
+            column: 0,
+            message: e.to_string(),
+        })?;
+        vec.push(T::try_from_record(&record)?);
+    }
+    Ok(vec)
+}
+
+/// Parse the field of `record` at `index`, counted from 0.
+pub fn parse_field<T>(record: &csv::StringRecord, index: usize) -> Result<T, Error>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    let error = |message: String| Error {
+        row: record.position().map_or(0, |p| p.line()),
+        column: index + 1,
+        message,
+    };
+
+    let field = record
+        .get(index)
+        .ok_or_else(|| error("missing".to_string()))?;
+    field
+        .parse()
+        .map_err(|e: T::Err| error(format!("{:?}: {}", field, e)))
+}
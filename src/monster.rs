@@ -0,0 +1,324 @@
+use crate::battle::combatant::{Combatant, CombatantInit, Element, Resistances};
+use crate::battle::Character;
+use crate::loader;
+
+use enum_iterator::IntoEnumIterator;
+use enum_map::Enum;
+use serde::{Deserialize, Serialize};
+
+/// An action in the action table of a monster.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Deserialize,
+    Display,
+    Enum,
+    EnumString,
+    IntoEnumIterator,
+    PartialEq,
+    Eq,
+    Serialize,
+)]
+pub enum MonsterAction {
+    #[strum(serialize = "こうげき")]
+    Attack,
+    #[strum(serialize = "つうこんのいちげき")]
+    HeavyBlow,
+    #[strum(serialize = "ぼうぎょ")]
+    Defend,
+    #[strum(serialize = "にげる")]
+    Flee,
+    #[strum(serialize = "なかまをよぶ")]
+    CallForHelp,
+    #[strum(serialize = "メラ")]
+    Frizz,
+    #[strum(serialize = "ギラ")]
+    Sizz,
+    #[strum(serialize = "ホイミ")]
+    Heal,
+    #[strum(serialize = "ラリホー")]
+    Snooze,
+    #[strum(serialize = "マヌーサ")]
+    Dazzle,
+    #[strum(serialize = "マホトーン")]
+    Fizzle,
+    #[strum(serialize = "ほのお")]
+    FireBreath,
+    #[strum(serialize = "ふぶき")]
+    IceBreath,
+}
+
+/// The number of actions in an action table.
+pub const ACTIONS: usize = 6;
+
+#[derive(Debug)]
+pub struct MonsterEntry {
+    name: String,
+    hp: u16,
+    mp: u16,
+    attack: i16,
+    defense: i16,
+    agility: u16,
+    evasion: u8,
+    exp: u32,
+    gold: u16,
+    drop_item: Option<String>,
+    drop_denominator: u16,
+    resistances: Resistances,
+    actions: [MonsterAction; ACTIONS],
+}
+
+impl MonsterEntry {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn hp(&self) -> u16 {
+        self.hp
+    }
+
+    pub fn mp(&self) -> u16 {
+        self.mp
+    }
+
+    pub fn attack(&self) -> i16 {
+        self.attack
+    }
+
+    pub fn defense(&self) -> i16 {
+        self.defense
+    }
+
+    pub fn agility(&self) -> u16 {
+        self.agility
+    }
+
+    /// Return the `rand()` threshold to dodge a physical attack, i.e. in 1/256.
+    pub fn evasion(&self) -> u8 {
+        self.evasion
+    }
+
+    pub fn exp(&self) -> u32 {
+        self.exp
+    }
+
+    pub fn gold(&self) -> u16 {
+        self.gold
+    }
+
+    /// Return the item dropped with the probability of 1 / `drop_denominator()`.
+    pub fn drop_item(&self) -> Option<&str> {
+        self.drop_item.as_deref()
+    }
+
+    pub fn drop_denominator(&self) -> u16 {
+        self.drop_denominator
+    }
+
+    /// Return the probability to drop the item.
+    pub fn drop_rate(&self) -> f64 {
+        if self.drop_item.is_none() || self.drop_denominator == 0 {
+            return 0.0;
+        }
+        1.0 / self.drop_denominator as f64
+    }
+
+    pub fn resistance(&self, element: Element) -> u8 {
+        self.resistances[element]
+    }
+
+    pub fn resistances(&self) -> &Resistances {
+        &self.resistances
+    }
+
+    pub fn actions(&self) -> &[MonsterAction; ACTIONS] {
+        &self.actions
+    }
+}
+
+impl loader::TryFromRecord for MonsterEntry {
+    fn try_from_record(record: &csv::StringRecord) -> Result<Self, loader::Error> {
+        // Parse in the order of the columns, so that the first error is reported.
+        let name = loader::parse_field(record, 0)?;
+        let hp = loader::parse_field(record, 1)?;
+        let mp = loader::parse_field(record, 2)?;
+        let attack = loader::parse_field(record, 3)?;
+        let defense = loader::parse_field(record, 4)?;
+        let agility = loader::parse_field(record, 5)?;
+        let evasion = loader::parse_field(record, 6)?;
+        let exp = loader::parse_field(record, 7)?;
+        let gold = loader::parse_field(record, 8)?;
+        let drop_item = match loader::parse_field::<String>(record, 9)? {
+            item if item.is_empty() => None,
+            item => Some(item),
+        };
+        let drop_denominator = loader::parse_field(record, 10)?;
+
+        let mut resistances = Resistances::default();
+        for (i, element) in Element::into_enum_iter().enumerate() {
+            resistances[element] = loader::parse_field(record, 11 + i)?;
+        }
+
+        let offset = 11 + Element::ITEM_COUNT;
+        let mut actions = [MonsterAction::Attack; ACTIONS];
+        for (i, action) in actions.iter_mut().enumerate() {
+            *action = loader::parse_field(record, offset + i)?;
+        }
+
+        Ok(Self {
+            name,
+            hp,
+            mp,
+            attack,
+            defense,
+            agility,
+            evasion,
+            exp,
+            gold,
+            drop_item,
+            drop_denominator,
+            resistances,
+            actions,
+        })
+    }
+}
+
+/// Build a monster in full HP and MP.
+impl From<&MonsterEntry> for Combatant {
+    fn from(entry: &MonsterEntry) -> Self {
+        CombatantInit {
+            name: entry.name.clone(),
+            side: Character::Monster,
+            max_hp: entry.hp,
+            max_mp: entry.mp,
+            attack: entry.attack,
+            defense: entry.defense,
+            agility: entry.agility,
+            evasion: entry.evasion,
+            resistances: entry.resistances,
+            ..Default::default()
+        }
+        .init()
+    }
+}
+
+/// Load monster entries from CSV with a header row.
+///
+/// The columns are 名前, HP, MP, 攻撃力, 守備力, すばやさ, 回避率, 経験値,
+/// ゴールド, ドロップ, ドロップ率分母, a resistance column for each `Element` in
+/// order and `ACTIONS` action columns, e.g. こうげき. 回避率 is in 1/256, and
+/// ドロップ is empty for no item. The table of the game is yet to be dumped from
+/// the ROM like the job and personality tables, so the caller provides one.
+///
+/// Return the row and column of the first field which fails to parse, e.g. a
+/// 回避率 over 255.
+pub fn load_monsters(data: &str) -> Result<Vec<MonsterEntry>, loader::Error> {
+    loader::try_from_csv(data)
+}
+
+/// Return the entry named `name` in Japanese, e.g. "スライム".
+pub fn find_monster<'a>(entries: &'a [MonsterEntry], name: &str) -> Option<&'a MonsterEntry> {
+    entries.iter().find(|entry| entry.name == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Made-up entries to test the loader, not the data of the game.
+    const DATA: &str =
+        "名前,HP,MP,攻撃力,守備力,すばやさ,回避率,経験値,ゴールド,ドロップ,ドロップ率分母,\
+耐性メラ,耐性ギラ,耐性ヒャド,耐性イオ,耐性バギ,耐性デイン,耐性ザキ,耐性ラリホー,耐性マヌーサ,\
+耐性マホトーン,耐性ニフラム,耐性ほのお,耐性ふぶき,行動1,行動2,行動3,行動4,行動5,行動6
+テストA,10,2,12,6,8,4,5,3,やくそう,64,0,0,0,0,0,0,1,0,0,0,2,0,0,\
+こうげき,こうげき,ギラ,こうげき,つうこんのいちげき,にげる
+テストB,30,0,20,15,4,255,12,8,,0,0,0,0,0,0,0,0,0,0,0,0,0,3,\
+こうげき,こうげき,こうげき,こうげき,こうげき,こうげき
+";
+
+    #[test]
+    fn test_load_monsters() {
+        let entries = load_monsters(DATA).unwrap();
+        assert_eq!(entries.len(), 2);
+
+        let a = &entries[0];
+        assert_eq!(a.name(), "テストA");
+        assert_eq!(a.hp(), 10);
+        assert_eq!(a.mp(), 2);
+        assert_eq!(a.attack(), 12);
+        assert_eq!(a.defense(), 6);
+        assert_eq!(a.agility(), 8);
+        assert_eq!(a.evasion(), 4);
+        assert_eq!(a.exp(), 5);
+        assert_eq!(a.gold(), 3);
+        assert_eq!(a.drop_item(), Some("やくそう"));
+        assert_eq!(a.drop_rate(), 1.0 / 64.0);
+        assert_eq!(a.resistance(Element::Whack), 1);
+        assert_eq!(a.resistance(Element::Expel), 2);
+        assert_eq!(
+            a.actions(),
+            &[
+                MonsterAction::Attack,
+                MonsterAction::Attack,
+                MonsterAction::Sizz,
+                MonsterAction::Attack,
+                MonsterAction::HeavyBlow,
+                MonsterAction::Flee
+            ]
+        );
+
+        let b = &entries[1];
+        assert_eq!(b.evasion(), 255);
+        assert_eq!(b.drop_item(), None);
+        assert_eq!(b.drop_rate(), 0.0);
+        assert_eq!(b.resistance(Element::IceBreath), 3);
+    }
+
+    #[test]
+    fn test_load_bad_monster() {
+        let header = DATA.lines().next().unwrap();
+        let row = DATA.lines().nth(1).unwrap();
+
+        let data = format!("{}\n{}\n{}\n", header, row, row.replacen(",4,", ",256,", 1));
+        let error = load_monsters(&data).err().unwrap();
+        assert_eq!((error.row, error.column), (3, 7));
+        assert_eq!(
+            error.to_string(),
+            "row 3, column 7: \"256\": number too large to fit in target type"
+        );
+
+        let data = format!("{}\n{}\n", header, row.replacen(",こうげき", ",まほう", 1));
+        let error = load_monsters(&data).err().unwrap();
+        assert_eq!((error.row, error.column), (2, 25));
+
+        let data = format!("{}\n{}\n", header, "テストC,10,2");
+        let error = load_monsters(&data).err().unwrap();
+        assert_eq!(
+            (error.row, error.column, error.message.as_str()),
+            (2, 4, "missing")
+        );
+    }
+
+    #[test]
+    fn test_find_monster() {
+        let entries = load_monsters(DATA).unwrap();
+
+        assert_eq!(find_monster(&entries, "テストB").unwrap().hp(), 30);
+        assert!(find_monster(&entries, "りゅうおう").is_none());
+    }
+
+    #[test]
+    fn test_to_combatant() {
+        let entries = load_monsters(DATA).unwrap();
+        let combatant = Combatant::from(&entries[0]);
+
+        assert_eq!(combatant.name, "テストA");
+        assert_eq!(combatant.side, Character::Monster);
+        assert_eq!(combatant.hp, 10);
+        assert_eq!(combatant.mp, 2);
+        assert_eq!(combatant.defense, 6);
+        assert_eq!(combatant.evasion, 4);
+        assert_eq!(combatant.resistances[Element::Whack], 1);
+    }
+}
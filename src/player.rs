@@ -1,7 +1,7 @@
 use crate::attr::{attrs_new, Attr, AttrValue, Attrs};
 use crate::job::{get_job_entry, Job};
 use crate::personality::{get_personality_table, Personality};
-use crate::rand;
+use crate::rand::{self, RandomSource};
 use crate::sex::Sex;
 
 use enum_iterator::IntoEnumIterator;
@@ -49,13 +49,12 @@ impl Player {
         self.job
     }
 
-    fn growth_attr(&self, lv: u8, attr: Attr) -> AttrValue {
+    fn growth_attr<R: RandomSource>(&self, lv: u8, attr: Attr, rng: &mut R) -> AttrValue {
         let increment_base = get_job_entry(self.job).attr_increment(lv, attr);
 
-        let mut rng = rand::thread_rng();
-
         let range = get_job_entry(self.job()).range_attr(self.lv, attr);
         let upper = range.max().unwrap();
+        rng.set_tag(format_args!("levelup {:?}", attr));
         if self.attr(attr) > upper {
             return (rng.rand() % 2).into();
         }
@@ -71,13 +70,18 @@ impl Player {
     }
 
     pub fn levelup(&mut self) {
+        self.levelup_with(&mut rand::thread_rng());
+    }
+
+    /// Same as `levelup`, but draws random numbers from `rng`.
+    pub fn levelup_with<R: RandomSource>(&mut self, rng: &mut R) {
         self.lv += 1;
 
         for attr in Attr::into_enum_iter() {
             let before = self.attrs[attr];
             let range = get_job_entry(self.job()).range_attr(self.lv, attr);
 
-            let mut after = before.saturating_add(self.growth_attr(self.lv, attr));
+            let mut after = before.saturating_add(self.growth_attr(self.lv, attr, rng));
 
             let lower = range.min().unwrap().to_fixed();
 
@@ -112,17 +116,23 @@ impl Player {
     }
 }
 
-fn init_maxhp_or_maxmp(vit_or_int: u8) -> u16 {
-    let mut rng = rand::thread_rng();
-
+fn init_maxhp_or_maxmp<R: RandomSource>(vit_or_int: u8, rng: &mut R) -> u16 {
     ((vit_or_int as u32) * (500 + rng.rand_by_multiply(25) as u32) / 256) as u16
 }
 
 pub fn init_attr_of_hero(sex: Sex, personality: Personality) -> Player {
+    init_attr_of_hero_with(sex, personality, &mut rand::thread_rng())
+}
+
+/// Same as `init_attr_of_hero`, but draws random numbers from `rng`.
+pub fn init_attr_of_hero_with<R: RandomSource>(
+    sex: Sex,
+    personality: Personality,
+    rng: &mut R,
+) -> Player {
     let job = Job::Hero;
     let job_entry = get_job_entry(job);
     let personality_entry = get_personality_table(personality);
-    let mut rng = rand::thread_rng();
     let mut attrs = EnumMap::<Attr, AttrValue>::default();
 
     for attr in Attr::into_enum_iter() {
@@ -137,8 +147,8 @@ pub fn init_attr_of_hero(sex: Sex, personality: Personality) -> Player {
         attrs[attr] = v;
     }
 
-    let max_hp = init_maxhp_or_maxmp(attrs[Attr::Vit].to_num());
-    let max_mp = init_maxhp_or_maxmp(attrs[Attr::Int].to_num());
+    let max_hp = init_maxhp_or_maxmp(attrs[Attr::Vit].to_num(), rng);
+    let max_mp = init_maxhp_or_maxmp(attrs[Attr::Int].to_num(), rng);
 
     Player {
         lv: 1,
@@ -203,6 +213,7 @@ impl PlayerInit {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::rand::State;
 
     #[test]
     fn test_init() {
@@ -226,4 +237,111 @@ mod tests {
         assert_eq!(player.personality, Personality::Ordinary);
         assert_eq!(player.job, Job::Soldier);
     }
+
+    #[test]
+    fn test_growth_attr_by_sequence() {
+        let player = PlayerInit {
+            lv: 1,
+            pow: 9,
+            spd: 2,
+            vit: 19,
+            int: 2,
+            lck: 3,
+            personality: Personality::Ordinary,
+            job: Job::Soldier,
+            ..Default::default()
+        }
+        .init();
+
+        // 16 values of 0 make the multinomial value 136, so the growth is
+        // the increment base (3 for Pow at Lv2) * 136 / 128 = 51 / 16.
+        let mut rng = rand::SequenceRng::new(vec![0; 16]);
+        let growth = player.growth_attr(2, Attr::Pow, &mut rng);
+
+        assert_eq!(rng.consumed(), 16);
+        assert_eq!(growth, AttrValue::from_num(3.1875));
+    }
+
+    #[test]
+    fn test_init_attr_of_hero_by_sequence() {
+        let mut rng = rand::SequenceRng::new(vec![1, 1, 1, 1, 1, 0, 0]);
+        let player = init_attr_of_hero_with(Sex::Man, Personality::Ordinary, &mut rng);
+
+        let mut rng = rand::SequenceRng::new(vec![0, 0, 0, 0, 0, 0, 0]);
+        let player_low = init_attr_of_hero_with(Sex::Man, Personality::Ordinary, &mut rng);
+
+        for attr in Attr::into_enum_iter() {
+            assert_eq!(player.attr(attr), player_low.attr(attr) + 1);
+        }
+        assert_eq!(rng.consumed(), 7);
+    }
+
+    #[test]
+    fn test_levelup_with() {
+        let player = PlayerInit {
+            lv: 1,
+            pow: 9,
+            spd: 2,
+            vit: 19,
+            int: 2,
+            lck: 3,
+            personality: Personality::Tough,
+            job: Job::Soldier,
+            ..Default::default()
+        }
+        .init();
+
+        let mut player0 = player.clone();
+        let mut rng0 = rand::Rng::from(0x12345678);
+        let mut player1 = player.clone();
+        let mut rng1 = rand::Rng::from(0x12345678);
+        for _ in 0..10 {
+            player0.levelup_with(&mut rng0);
+            rand::thread_rng().rand();
+            player1.levelup_with(&mut rng1);
+        }
+
+        assert_eq!(player0.level(), 11);
+        assert_eq!(player0.attrs, player1.attrs);
+        assert_eq!(rng0.state(), rng1.state());
+    }
+
+    #[test]
+    fn test_levelup_nested_thread_rng() {
+        let mut player = PlayerInit {
+            lv: 1,
+            vit: 19,
+            ..Default::default()
+        }
+        .init();
+        let mut expected = player.clone();
+        let mut rng = rand::Rng::from(0x12345678);
+        expected.levelup_with(&mut rng);
+
+        let mut thread_rng = rand::thread_rng();
+        thread_rng.set_state(State::new(0x12345678));
+        player.levelup();
+
+        assert_eq!(player.attrs, expected.attrs);
+        assert_eq!(thread_rng.state(), rng.state());
+    }
+
+    #[test]
+    fn test_init_attr_of_hero_nested_thread_rng() {
+        let mut rng = rand::Rng::from(0x12345678);
+        let expected = init_attr_of_hero_with(Sex::Women, Personality::Tough, &mut rng);
+
+        let mut thread_rng = rand::thread_rng();
+        thread_rng.set_state(State::new(0x12345678));
+        let player = init_attr_of_hero(Sex::Women, Personality::Tough);
+
+        assert_eq!(player.attrs, expected.attrs);
+        assert_eq!(player.max_hp, expected.max_hp);
+        assert_eq!(player.max_mp, expected.max_mp);
+        assert_eq!(thread_rng.state(), rng.state());
+
+        // The handle stays usable after the nested uses.
+        thread_rng.rand();
+        assert_eq!(rand::thread_rng().state(), rng.state().next());
+    }
 }
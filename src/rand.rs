@@ -1,9 +1,25 @@
 use crate::bit;
 
+pub mod analysis;
+pub mod batch;
+pub mod branch;
+pub mod frame;
+mod gf2;
+pub mod pmf;
+pub mod recovery;
+pub mod search;
+pub mod stats;
+pub mod trace;
+
+use gf2::Matrix;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use std::borrow::Borrow;
-use std::cell::UnsafeCell;
+use std::cell::RefCell;
+use std::convert::TryFrom;
 use std::fmt;
+use std::num::ParseIntError;
 use std::rc::Rc;
+use std::str::FromStr;
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
@@ -36,6 +52,23 @@ impl State {
         *self = State::next(self);
     }
 
+    /// Return the state after `n` transitions.
+    ///
+    /// The transition is linear over GF(2), so this composes precomputed
+    /// powers of its matrix and runs in O(log n).
+    pub fn advance(&self, n: u64) -> State {
+        State(apply_powers(&TRANSITION_POWERS, self.0, n))
+    }
+
+    /// Return whether some state transits to this state.
+    ///
+    /// The lowest byte of a next state is `bit[5..13] ^ bit[23..31]` of the
+    /// previous one, which forces `bit[0] == bit[13] ^ bit[31]`. Exactly half
+    /// of the states satisfy it, and each of them has two predecessors.
+    pub fn has_prev(&self) -> bool {
+        (self.0 ^ (self.0 >> 13) ^ (self.0 >> 31)) & 1 == 0
+    }
+
     pub fn rand(&self) -> u8 {
         return (self.0 & (u8::max_value() as u32)) as u8;
     }
@@ -55,6 +88,87 @@ impl State {
     }
 }
 
+impl State {
+    /// Return all states whose next state is this state.
+    ///
+    /// The transition drops bit 31, so the result is either empty or two
+    /// states which differ only in bit 31. The first one has a predecessor
+    /// itself (i.e. it lies on a cycle) and the second one does not.
+    /// Both share the lowest byte, i.e. the previous `rand()`.
+    pub fn prev(&self) -> Vec<State> {
+        if !self.has_prev() {
+            return vec![];
+        }
+
+        let prev = State(prev_on_cycle(self.0));
+        vec![prev, State(prev.0 ^ 0x80000000)]
+    }
+
+    /// Return the state `n` transitions before, or `None` if there is no such state.
+    ///
+    /// At every step the predecessor which lies on a cycle is chosen, so that
+    /// `rewind` can go back any number of steps. The other predecessor at the
+    /// last step differs only in bit 31, which never affects later states.
+    /// This runs in O(log n) like `advance`.
+    pub fn rewind(&self, n: u64) -> Option<State> {
+        if n > 0 && !self.has_prev() {
+            return None;
+        }
+
+        Some(State(apply_powers(&REWIND_POWERS, self.0, n)))
+    }
+
+    /// Return the number of transitions from this state to `other`, or `None`
+    /// if `other` is not reached within `limit` transitions.
+    ///
+    /// This takes O(sqrt(min(limit, 2^31))) steps and memory, see
+    /// `analysis::distance_within`.
+    pub fn distance_to(&self, other: &State, limit: u64) -> Option<u64> {
+        analysis::distance_within(*self, *other, limit)
+    }
+}
+
+/// The predecessor of `v` which has a predecessor itself.
+///
+/// Only meaningful when `v` has a predecessor. This map is linear.
+fn prev_on_cycle(v: u32) -> u32 {
+    let lower24 = v >> 8;
+    let upper7 = ((v ^ (v >> 13)) & 0xfe) << 23;
+    let prev = lower24 | upper7;
+    let bit31 = (prev ^ (prev >> 13)) & 1;
+
+    prev | (bit31 << 31)
+}
+
+fn powers_of(m: Matrix) -> Vec<Matrix> {
+    let mut powers = Vec::with_capacity(64);
+    let mut m = m;
+    for _ in 0..64 {
+        powers.push(m);
+        m = m.mul(&m);
+    }
+    powers
+}
+
+fn apply_powers(powers: &[Matrix], v: u32, n: u64) -> u32 {
+    let mut v = v;
+    for (i, power) in powers.iter().enumerate() {
+        if (n >> i) & 1 == 1 {
+            v = power.apply(v);
+        }
+    }
+    v
+}
+
+lazy_static! {
+    /// `TRANSITION_POWERS[i]` is the matrix of `2^i` transitions.
+    static ref TRANSITION_POWERS: Vec<Matrix> =
+        powers_of(Matrix::from_linear_map(|v| State(v).next().0));
+
+    /// `REWIND_POWERS[i]` is the matrix of `2^i` backward transitions along a cycle.
+    static ref REWIND_POWERS: Vec<Matrix> = powers_of(Matrix::from_linear_map(prev_on_cycle));
+}
+
 impl Default for State {
     fn default() -> Self {
         Self(0xaae21259)
@@ -73,6 +187,69 @@ impl From<u32> for State {
     }
 }
 
+/// Parse a state from hex with the prefix "0x", e.g. "0xaae21259", or decimal.
+impl FromStr for State {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+            Some(hex) => u32::from_str_radix(hex, 16)?,
+            None => s.parse()?,
+        };
+        Ok(Self(value))
+    }
+}
+
+/// Serialize as a hex string like `Display` in human-readable formats, or as
+/// the raw integer otherwise.
+impl Serialize for State {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            serializer.serialize_u32(self.0)
+        }
+    }
+}
+
+/// Deserialize from a string accepted by `FromStr` or from a raw integer.
+impl<'de> Deserialize<'de> for State {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct StateVisitor;
+
+        impl de::Visitor<'_> for StateVisitor {
+            type Value = State;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a 32-bit state as hex/decimal string or integer")
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<State, E> {
+                u32::try_from(v)
+                    .map(State)
+                    .map_err(|_| E::invalid_value(de::Unexpected::Unsigned(v), &self))
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<State, E> {
+                u32::try_from(v)
+                    .map(State)
+                    .map_err(|_| E::invalid_value(de::Unexpected::Signed(v), &self))
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<State, E> {
+                v.parse()
+                    .map_err(|_| E::invalid_value(de::Unexpected::Str(v), &self))
+            }
+        }
+
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(StateVisitor)
+        } else {
+            deserializer.deserialize_u32(StateVisitor)
+        }
+    }
+}
+
 impl AsRef<u32> for State {
     fn as_ref(&self) -> &u32 {
         &self.0
@@ -86,7 +263,7 @@ impl Borrow<u32> for State {
 }
 
 #[wasm_bindgen]
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct Rng {
     state: State,
 }
@@ -111,6 +288,38 @@ impl Rng {
         self.state.transit();
     }
 
+    /// Transit the state of RNG `n` times in O(log n).
+    pub fn skip(&mut self, n: u64) {
+        self.state = self.state.advance(n);
+    }
+
+    /// Transit the state of RNG backward `n` times in O(log n).
+    ///
+    /// Return false and keep the state if it cannot be rewound, i.e. the
+    /// current state has no predecessor. See `State::rewind`.
+    pub fn rewind(&mut self, n: u64) -> bool {
+        match self.state.rewind(n) {
+            Some(state) => {
+                self.state = state;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Advance the RNG by a frame.
+    ///
+    /// This method simulates the subroutine at 0x0006a1, which transits the
+    /// state once every frame and discards the result.
+    pub fn advance_frame(&mut self) {
+        self.transit();
+    }
+
+    /// Advance the RNG by `n` frames in O(log n).
+    pub fn advance_frames(&mut self, n: u64) {
+        self.skip(n);
+    }
+
     /// Return a 8-bit(i.e. [0, 255]) uniform random number.
     ///
     /// This method simulates the subroutine at 0x0012e3.
@@ -159,33 +368,165 @@ impl From<u32> for Rng {
     }
 }
 
+/// Each `u32` consists of 4 successive `rand()` (SR at 0x0012e3) values in
+/// little-endian order, i.e. the first value is the lowest byte, and each `u64`
+/// of two such `u32`s, lower first. `fill_bytes` writes one `rand()` value per
+/// byte, so it yields the same byte sequence as `next_u32`.
+impl rand_core::RngCore for Rng {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        rand_core::impls::next_u64_via_u32(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for byte in dest.iter_mut() {
+            *byte = self.rand();
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// The seed is the state value in little-endian bytes, and `seed_from_u64`
+/// takes the lower 32 bits as the state value.
+impl rand_core::SeedableRng for Rng {
+    type Seed = [u8; 4];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        Rng::from(u32::from_le_bytes(seed))
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        Rng::from(state as u32)
+    }
+}
+
+/// A source of random numbers in the way of the subroutines of the game.
+///
+/// Simulations are generic over this, so that they can be driven by `Rng`,
+/// `ThreadRng` or an exact sequence of values such as `SequenceRng`.
+pub trait RandomSource {
+    /// Return a 8-bit(i.e. [0, 255]) uniform random number.
+    ///
+    /// This method simulates the subroutine at 0x0012e3.
+    fn rand(&mut self) -> u8;
+
+    /// Return a 8-bit random number inbound [0, upper].
+    ///
+    /// This method simulates the subroutine at 0x00133e.
+    fn rand_by_multiply(&mut self, upper: u8) -> u8;
+
+    /// Return a 8-bit multinomial random number.
+    ///
+    /// This method simulates the subroutine at 0x0014d4.
+    fn rand_multinomial(&mut self, offset: u8, mask: u8) -> u8;
+
+    /// Tell which caller the following calls come from, e.g. "levelup Pow".
+    ///
+    /// The tag is formatted lazily, so this costs nothing for sources which
+    /// ignore it, as the default does. `TracingRng` records it.
+    fn set_tag(&mut self, _tag: fmt::Arguments) {}
+}
+
+impl RandomSource for Rng {
+    fn rand(&mut self) -> u8 {
+        Rng::rand(self)
+    }
+
+    fn rand_by_multiply(&mut self, upper: u8) -> u8 {
+        Rng::rand_by_multiply(self, upper)
+    }
+
+    fn rand_multinomial(&mut self, offset: u8, mask: u8) -> u8 {
+        Rng::rand_multinomial(self, offset, mask)
+    }
+}
+
+/// A random source replaying a given sequence of `rand()` values.
+///
+/// Each value stands for the lowest byte of a state, so `rand_by_multiply`
+/// uses one value and `rand_multinomial` uses 16 values in the same way as
+/// `Rng`. It panics when the sequence runs out.
+#[derive(Debug, Clone, Default)]
+pub struct SequenceRng {
+    values: Vec<u8>,
+    pos: usize,
+}
+
+impl SequenceRng {
+    pub fn new(values: Vec<u8>) -> Self {
+        Self { values, pos: 0 }
+    }
+
+    /// Return the number of values consumed so far.
+    pub fn consumed(&self) -> usize {
+        self.pos
+    }
+
+    fn next_state(&mut self) -> State {
+        let value = self.values[self.pos];
+        self.pos += 1;
+        State::from(value as u32)
+    }
+}
+
+impl RandomSource for SequenceRng {
+    fn rand(&mut self) -> u8 {
+        self.next_state().rand()
+    }
+
+    fn rand_by_multiply(&mut self, upper: u8) -> u8 {
+        self.next_state().rand_by_multiply(upper)
+    }
+
+    fn rand_multinomial(&mut self, offset: u8, mask: u8) -> u8 {
+        assert!(bit::is_powerof2(mask as u16 + 1));
+
+        let mut sum = offset as u16;
+        for _ in 0..16 {
+            sum += self.next_state().rand_by_mask(mask) as u16;
+        }
+
+        (sum & 0xff) as u8
+    }
+}
+
+/// A handle to the RNG shared in the current thread.
+///
+/// Every method borrows the shared RNG only while it runs, so any number of
+/// handles can be used in turn, even in nested calls.
 #[derive(Clone, Debug)]
 pub struct ThreadRng {
-    rng: Rc<UnsafeCell<Rng>>,
+    rng: Rc<RefCell<Rng>>,
 }
 
 impl ThreadRng {
     pub fn state(&self) -> State {
-        unsafe { (*self.rng.get()).state() }
+        (*self.rng).borrow().state()
     }
 
     pub fn set_state(&mut self, state: State) {
-        let rng = unsafe { &mut *self.rng.get() };
-        rng.set_state(state);
+        self.rng.borrow_mut().set_state(state);
     }
 
     /// Transit the state of RNG.
     pub fn transit(&mut self) {
-        let rng = unsafe { &mut *self.rng.get() };
-        rng.state.transit();
+        self.rng.borrow_mut().transit();
     }
 
     /// Return a 8-bit(i.e. [0, 255]) uniform random number.
     ///
     /// This method simulates the subroutine at 0x0012e3.
     pub fn rand(&mut self) -> u8 {
-        let rng = unsafe { &mut *self.rng.get() };
-        rng.rand()
+        self.rng.borrow_mut().rand()
     }
 
     /// Return a 8-bit random number inbound [0, upper].
@@ -194,8 +535,7 @@ impl ThreadRng {
     ///
     /// This method simulates the subroutine at 0x00133e.
     pub fn rand_by_multiply(&mut self, upper: u8) -> u8 {
-        let rng = unsafe { &mut *self.rng.get() };
-        rng.rand_by_multiply(upper)
+        self.rng.borrow_mut().rand_by_multiply(upper)
     }
 
     /// Return a 8-bit multinomial random number.
@@ -204,13 +544,26 @@ impl ThreadRng {
     ///
     /// This method simulates the subroutine at 0x0014d4.
     pub fn rand_multinomial(&mut self, offset: u8, mask: u8) -> u8 {
-        let rng = unsafe { &mut *self.rng.get() };
-        rng.rand_multinomial(offset, mask)
+        self.rng.borrow_mut().rand_multinomial(offset, mask)
+    }
+}
+
+impl RandomSource for ThreadRng {
+    fn rand(&mut self) -> u8 {
+        ThreadRng::rand(self)
+    }
+
+    fn rand_by_multiply(&mut self, upper: u8) -> u8 {
+        ThreadRng::rand_by_multiply(self, upper)
+    }
+
+    fn rand_multinomial(&mut self, offset: u8, mask: u8) -> u8 {
+        ThreadRng::rand_multinomial(self, offset, mask)
     }
 }
 
 thread_local! {
-    static THREAD_RNG_KEY: Rc<UnsafeCell<Rng>> = Rc::new(UnsafeCell::new(Rng::default()));
+    static THREAD_RNG_KEY: Rc<RefCell<Rng>> = Rc::new(RefCell::new(Rng::default()));
 }
 
 pub fn thread_rng() -> ThreadRng {
@@ -238,6 +591,48 @@ mod tests {
         assert_eq!(format!("{}", rng.state()), "0xaae21259");
     }
 
+    #[test]
+    fn test_state_from_str() {
+        assert_eq!("0xaae21259".parse(), Ok(State::default()));
+        assert_eq!("0XAAE21259".parse(), Ok(State::default()));
+        assert_eq!("2866942553".parse(), Ok(State::default()));
+        assert!("0x1aae21259".parse::<State>().is_err());
+        assert!("aae21259".parse::<State>().is_err());
+        assert!("".parse::<State>().is_err());
+    }
+
+    #[test]
+    fn test_state_serde() {
+        let state = State::default();
+
+        assert_eq!(serde_json::to_string(&state).unwrap(), r#""0xaae21259""#);
+        assert_eq!(
+            serde_json::from_str::<State>(r#""0xaae21259""#).unwrap(),
+            state
+        );
+        assert_eq!(
+            serde_json::from_str::<State>(r#""2866942553""#).unwrap(),
+            state
+        );
+        assert_eq!(serde_json::from_str::<State>("2866942553").unwrap(), state);
+        assert!(serde_json::from_str::<State>("4294967296").is_err());
+        assert!(serde_json::from_str::<State>("-1").is_err());
+        assert!(serde_json::from_str::<State>(r#""xyz""#).is_err());
+    }
+
+    #[test]
+    fn test_rng_serde() {
+        let mut rng = Rng::from(0x12345678);
+        rng.rand();
+
+        let json = serde_json::to_string(&rng).unwrap();
+        assert_eq!(json, r#"{"state":"0x34567897"}"#);
+
+        let mut restored: Rng = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.state(), rng.state());
+        assert_eq!(restored.rand(), rng.rand());
+    }
+
     #[test]
     fn test_rng_clone() {
         let rng = Rng::default();
@@ -279,6 +674,233 @@ mod tests {
         rng.rand_multinomial(0, mask);
     }
 
+    #[test]
+    fn test_state_advance() {
+        let mut state = State::default();
+
+        for n in 0..1000 {
+            assert_eq!(State::default().advance(n), state);
+            state.transit();
+        }
+    }
+
+    #[test]
+    fn test_state_advance_far() {
+        let n = 1_000_000;
+        let mut state = State::new(0x12345678);
+        for _ in 0..n {
+            state.transit();
+        }
+
+        assert_eq!(State::new(0x12345678).advance(n), state);
+        assert_eq!(
+            State::new(0x12345678).advance(n).advance(n),
+            State::new(0x12345678).advance(2 * n)
+        );
+    }
+
+    #[test]
+    fn test_rng_skip() {
+        let mut rng = Rng::default();
+        let mut rng_skipped = Rng::default();
+
+        for _ in 0..12345 {
+            rng.transit();
+        }
+        rng_skipped.skip(12345);
+
+        assert_eq!(rng_skipped.state(), rng.state());
+        assert_eq!(rng_skipped.rand(), rng.rand());
+    }
+
+    #[test]
+    fn test_advance_frames() {
+        let mut rng = Rng::default();
+        let mut rng_by_frame = Rng::default();
+
+        rng.advance_frames(60);
+        for _ in 0..60 {
+            rng_by_frame.advance_frame();
+        }
+
+        assert_eq!(rng.state(), rng_by_frame.state());
+        assert_eq!(rng.state(), State::default().advance(60));
+    }
+
+    #[test]
+    fn test_sequence_rng() {
+        let mut rng = Rng::default();
+        let values: Vec<u8> = (0..18).map(|_| rng.rand()).collect();
+
+        let mut rng = Rng::default();
+        let mut seq = SequenceRng::new(values);
+        assert_eq!(
+            RandomSource::rand_by_multiply(&mut seq, 16),
+            rng.rand_by_multiply(16)
+        );
+        assert_eq!(
+            RandomSource::rand_multinomial(&mut seq, 136, 31),
+            rng.rand_multinomial(136, 31)
+        );
+        assert_eq!(RandomSource::rand(&mut seq), rng.rand());
+        assert_eq!(seq.consumed(), 18);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_sequence_rng_exhausted() {
+        let mut seq = SequenceRng::new(vec![0; 15]);
+
+        seq.rand_multinomial(6, 0xf);
+    }
+
+    #[test]
+    fn test_thread_rng_shared() {
+        let mut rng0 = thread_rng();
+        let mut rng1 = thread_rng();
+        rng0.set_state(State::default());
+
+        rng0.rand();
+        assert_eq!(rng1.state(), State::default().next());
+        rng1.rand_multinomial(136, 31);
+        assert_eq!(rng0.state(), State::default().advance(17));
+    }
+
+    #[test]
+    fn test_rng_core() {
+        use rand_core::RngCore;
+
+        let mut rng = Rng::default();
+        let rands: Vec<u8> = (0..12).map(|_| rng.rand()).collect();
+
+        let mut rng = Rng::default();
+        assert_eq!(
+            rng.next_u32(),
+            u32::from_le_bytes([rands[0], rands[1], rands[2], rands[3]])
+        );
+        assert_eq!(
+            rng.next_u64(),
+            u64::from_le_bytes([
+                rands[4], rands[5], rands[6], rands[7], rands[8], rands[9], rands[10], rands[11]
+            ])
+        );
+
+        let mut rng = Rng::default();
+        let mut bytes = [0; 12];
+        rng.fill_bytes(&mut bytes);
+        assert_eq!(bytes.to_vec(), rands);
+    }
+
+    #[test]
+    fn test_rand_api() {
+        use ::rand::seq::SliceRandom;
+        use ::rand::Rng as _;
+        use rand_core::RngCore;
+
+        let mut rng = Rng::default();
+        let value = rng.next_u32();
+        let mut rng = Rng::default();
+        assert_eq!(rng.gen::<u32>(), value);
+
+        let shuffle = |state| {
+            let mut values: Vec<u8> = (0..16).collect();
+            values.shuffle(&mut Rng::from(state));
+            values
+        };
+        let values = shuffle(0x12345678);
+        assert_eq!(values, shuffle(0x12345678));
+        assert_ne!(values, shuffle(0x87654321));
+
+        let mut sorted = values.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..16).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn test_seedable_rng() {
+        use rand_core::SeedableRng;
+
+        assert_eq!(
+            Rng::from_seed([0x59, 0x12, 0xe2, 0xaa]).state(),
+            State::default()
+        );
+        assert_eq!(Rng::seed_from_u64(0xaae21259).state(), State::default());
+    }
+
+    #[test]
+    fn test_state_prev() {
+        for v in [
+            0, 1, 0x80000000, 0xaae21259, 0xe21259c7, 0x12345678, 0xffffffff,
+        ]
+        .iter()
+        {
+            let state = State::new(*v);
+            let next = state.next();
+            let prevs = next.prev();
+
+            assert!(next.has_prev());
+            assert_eq!(prevs.len(), 2);
+            assert!(prevs.contains(&state));
+            assert!(prevs.iter().all(|prev| prev.next() == next));
+            assert!(prevs[0].has_prev());
+            assert!(!prevs[1].has_prev());
+        }
+    }
+
+    #[test]
+    fn test_state_prev_none() {
+        // bit[0] != bit[13] ^ bit[31]
+        let state = State::new(0x00000001);
+
+        assert!(!state.has_prev());
+        assert!(state.prev().is_empty());
+        assert_eq!(state.rewind(0), Some(state));
+        assert_eq!(state.rewind(1), None);
+    }
+
+    #[test]
+    fn test_state_rewind() {
+        let state = State::default();
+
+        for n in [0, 1, 2, 100, 12345, 1 << 40].iter() {
+            assert_eq!(state.advance(*n).rewind(*n), Some(state));
+            assert_eq!(state.rewind(*n).unwrap().advance(*n), state);
+        }
+    }
+
+    #[test]
+    fn test_state_distance_to() {
+        let state = State::default();
+
+        assert_eq!(state.distance_to(&state.advance(1000), 1000), Some(1000));
+        assert_eq!(state.distance_to(&state.advance(1000), 999), None);
+        assert_eq!(
+            state.distance_to(&state.advance(0x12345678), u64::MAX),
+            Some(0x12345678)
+        );
+        assert_eq!(
+            state.advance(1).distance_to(&state, u64::MAX),
+            Some((1 << 31) - 2)
+        );
+        assert_eq!(state.distance_to(&State::new(0), u64::MAX), None);
+    }
+
+    #[test]
+    fn test_rng_rewind() {
+        let mut rng = Rng::default();
+        let rands: Vec<u8> = (0..10).map(|_| rng.rand()).collect();
+
+        for rand in rands.iter().rev() {
+            assert_eq!(rng.state().rand(), *rand);
+            assert!(rng.rewind(1));
+        }
+        assert_eq!(rng.state(), State::default());
+
+        let mut rng = Rng::from(0x00000001);
+        assert!(!rng.rewind(1));
+        assert_eq!(rng.state(), State(0x00000001));
+    }
+
     #[test]
     fn test_state_hash() {
         let mut hist = vec![0; u32::max_value() as usize];
@@ -0,0 +1,333 @@
+use crate::rand::gf2::Matrix;
+use crate::rand::{State, TRANSITION_POWERS};
+
+use std::collections::HashMap;
+
+/// The number of states which have a predecessor, i.e. which lie on a cycle.
+pub const CYCLIC_STATES: u64 = 1 << 31;
+
+/// Cycles of the same period in the state space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycles {
+    pub period: u64,
+    pub count: u64,
+}
+
+/// Return whether the state is produced by a transition from some state.
+///
+/// Exactly half of the states are unreachable: the ones violating
+/// `bit[0] == bit[13] ^ bit[31]` (see `State::has_prev`).
+pub fn is_reachable(state: State) -> bool {
+    state.has_prev()
+}
+
+/// Return the number of transitions before the state enters its cycle.
+///
+/// The transition is a bijection on the reachable states, so every reachable
+/// state lies on a cycle and every unreachable one is a leaf of length 1.
+pub fn tail_length(state: State) -> u64 {
+    if is_reachable(state) {
+        0
+    } else {
+        1
+    }
+}
+
+/// Return the first state on the cycle which the state enters.
+pub fn cycle_entry(state: State) -> State {
+    if is_reachable(state) {
+        state
+    } else {
+        state.next()
+    }
+}
+
+/// Return the smallest number of transitions from `from` to `to`, or `None` if
+/// `to` is never reached from `from`.
+///
+/// This is a baby-step giant-step search, so it takes about 2^15.5 steps and
+/// as many entries of memory even when the states are 2^31 transitions apart.
+pub fn distance(from: State, to: State) -> Option<u64> {
+    distance_within(from, to, u64::MAX)
+}
+
+/// Return the smallest number of transitions from `from` to `to` if it is at
+/// most `limit`, or `None` otherwise.
+///
+/// Same as `distance`, but both the baby steps and the giant steps number
+/// about `sqrt(min(limit, 2^31))`.
+pub fn distance_within(from: State, to: State, limit: u64) -> Option<u64> {
+    if from == to {
+        return Some(0);
+    }
+    if limit == 0 {
+        return None;
+    }
+    if !is_reachable(from) {
+        return distance_within(from.next(), to, limit - 1).map(|n| n + 1);
+    }
+    if !is_reachable(to) {
+        return None;
+    }
+
+    // Find `n = i * baby_steps + j` such that `from.advance(j) == to.rewind(i * baby_steps)`.
+    let bound = u64::min(limit, CYCLIC_STATES);
+    let baby_steps = ceil_sqrt(bound + 1);
+    let mut baby = HashMap::new();
+    let mut state = from;
+    for j in 0..baby_steps {
+        baby.entry(state).or_insert(j);
+        state.transit();
+    }
+
+    let mut giant = to;
+    for i in 0..=bound / baby_steps {
+        if let Some(j) = baby.get(&giant) {
+            let n = i * baby_steps + j;
+            return if n <= limit { Some(n) } else { None };
+        }
+        giant = giant.rewind(baby_steps).unwrap();
+    }
+
+    None
+}
+
+/// Return the smallest `r` such that `r * r >= n`.
+fn ceil_sqrt(n: u64) -> u64 {
+    let mut r = (n as f64).sqrt() as u64;
+    while r * r < n {
+        r += 1;
+    }
+    while r > 0 && (r - 1) * (r - 1) >= n {
+        r -= 1;
+    }
+    r
+}
+
+/// Return whether the orbits of the states enter the same cycle.
+pub fn is_same_orbit(x: State, y: State) -> bool {
+    distance(cycle_entry(x), cycle_entry(y)).is_some()
+}
+
+/// Return the period of the cycle which the state enters.
+pub fn period(state: State) -> u64 {
+    let entry = cycle_entry(state);
+    distance(entry.next(), entry).unwrap() + 1
+}
+
+/// Return the cycle decomposition of the state space, in ascending order of period.
+///
+/// For each divisor `d` of the order of the transition, the number of states
+/// with `next^d(x) == x` is `2^(32 - rank(M^d + I))`, and Möbius inversion over
+/// the divisors gives the number of states on cycles of period exactly `d`.
+/// So this does not walk the 2^32 states.
+pub fn cycle_decomposition() -> Vec<Cycles> {
+    let transition = TRANSITION_POWERS[0];
+    let primes = factorize(order());
+
+    let fixed_points = |d: u64| 1_u64 << (32 - transition.pow(d).add(&Matrix::identity()).rank());
+
+    let mut decomposition = Vec::new();
+    for d in divisors(&primes) {
+        // Möbius inversion over the distinct primes dividing `d`.
+        let dividing: Vec<u64> = primes
+            .iter()
+            .map(|(p, _)| *p)
+            .filter(|p| d % p == 0)
+            .collect();
+        let mut states: i64 = 0;
+        for subset in 0..(1_u32 << dividing.len()) {
+            let divisor: u64 = dividing
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| (subset >> i) & 1 == 1)
+                .map(|(_, p)| *p)
+                .product();
+            let sign = if subset.count_ones() % 2 == 0 { 1 } else { -1 };
+            states += sign * fixed_points(d / divisor) as i64;
+        }
+
+        if states > 0 {
+            decomposition.push(Cycles {
+                period: d,
+                count: states as u64 / d,
+            });
+        }
+    }
+
+    decomposition.sort_by_key(|c| c.period);
+    decomposition
+}
+
+/// Return the smallest `n > 0` such that `next^n` is the identity on the cycles.
+fn order() -> u64 {
+    // The images of the unit vectors span the reachable states.
+    let mut order = 1;
+    for i in 0..32 {
+        let state = State::new(1 << i).next();
+        if state.advance(order) != state {
+            order = lcm(order, period(state));
+        }
+    }
+    order
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn lcm(a: u64, b: u64) -> u64 {
+    a / gcd(a, b) * b
+}
+
+/// Return the prime factors with their exponents.
+fn factorize(n: u64) -> Vec<(u64, u32)> {
+    let mut factors = Vec::new();
+    let mut rest = n;
+    let mut p = 2;
+    while p * p <= rest {
+        let mut e = 0;
+        while rest.is_multiple_of(p) {
+            rest /= p;
+            e += 1;
+        }
+        if e > 0 {
+            factors.push((p, e));
+        }
+        p += 1;
+    }
+    if rest > 1 {
+        factors.push((rest, 1));
+    }
+    factors
+}
+
+fn divisors(primes: &[(u64, u32)]) -> Vec<u64> {
+    let mut divisors = vec![1];
+    for (p, e) in primes {
+        let mut next = Vec::new();
+        for d in &divisors {
+            let mut v = *d;
+            for _ in 0..=*e {
+                next.push(v);
+                v *= p;
+            }
+        }
+        divisors = next;
+    }
+    divisors.sort();
+    divisors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tail_length() {
+        assert_eq!(tail_length(State::default()), 0);
+        assert_eq!(tail_length(State::new(0x00000001)), 1);
+        assert!(!is_reachable(State::new(0x00000001)));
+        assert_eq!(
+            cycle_entry(State::new(0x00000001)),
+            State::new(0x00000001).next()
+        );
+    }
+
+    #[test]
+    fn test_distance() {
+        let state = State::default();
+
+        assert_eq!(distance(state, state), Some(0));
+        assert_eq!(distance(state, state.next()), Some(1));
+        assert_eq!(distance(state, state.advance(123456789)), Some(123456789));
+        assert_eq!(
+            distance(
+                State::new(0x00000001),
+                State::new(0x00000001).advance(70000)
+            ),
+            Some(70000)
+        );
+        assert_eq!(distance(state, State::new(0x00000001)), None);
+        assert_eq!(distance(state, State::new(0)), None);
+    }
+
+    #[test]
+    fn test_distance_within() {
+        let state = State::default();
+        let far = state.advance(123456789);
+
+        assert_eq!(distance_within(state, state, 0), Some(0));
+        assert_eq!(distance_within(state, state.next(), 0), None);
+        assert_eq!(distance_within(state, state.advance(10), 10), Some(10));
+        assert_eq!(distance_within(state, state.advance(10), 9), None);
+        assert_eq!(distance_within(state, far, 123456789), Some(123456789));
+        assert_eq!(distance_within(state, far, 123456788), None);
+        assert_eq!(distance_within(state, far, 1000), None);
+
+        // An unreachable state takes one transition to enter its cycle.
+        let leaf = State::new(0x00000001);
+        assert!(!is_reachable(leaf));
+        assert_eq!(distance_within(leaf, leaf.advance(5), 5), Some(5));
+        assert_eq!(distance_within(leaf, leaf.advance(5), 4), None);
+    }
+
+    #[test]
+    fn test_ceil_sqrt() {
+        assert_eq!(ceil_sqrt(0), 0);
+        assert_eq!(ceil_sqrt(1), 1);
+        assert_eq!(ceil_sqrt(2), 2);
+        assert_eq!(ceil_sqrt(16), 4);
+        assert_eq!(ceil_sqrt(17), 5);
+        assert_eq!(ceil_sqrt(CYCLIC_STATES + 1), 46341);
+    }
+
+    #[test]
+    fn test_period() {
+        assert_eq!(period(State::default()), (1 << 31) - 1);
+        assert_eq!(period(State::new(0)), 1);
+        assert_eq!(period(State::new(0x80000000)), 1);
+    }
+
+    #[test]
+    fn test_is_same_orbit() {
+        assert!(is_same_orbit(State::default(), State::new(0x12345678)));
+        assert!(!is_same_orbit(State::default(), State::new(0x80000000)));
+    }
+
+    #[test]
+    fn test_cycle_decomposition() {
+        let decomposition = cycle_decomposition();
+
+        assert_eq!(
+            decomposition,
+            vec![
+                Cycles {
+                    period: 1,
+                    count: 1
+                },
+                Cycles {
+                    period: (1 << 31) - 1,
+                    count: 1
+                },
+            ]
+        );
+        assert_eq!(
+            decomposition
+                .iter()
+                .map(|c| c.period * c.count)
+                .sum::<u64>(),
+            CYCLIC_STATES
+        );
+    }
+
+    #[test]
+    fn test_divisors() {
+        assert_eq!(factorize(360), vec![(2, 3), (3, 2), (5, 1)]);
+        assert_eq!(divisors(&factorize(12)), vec![1, 2, 3, 4, 6, 12]);
+    }
+}
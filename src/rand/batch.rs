@@ -0,0 +1,368 @@
+use crate::bit;
+use crate::rand::State;
+
+use std::ops::{BitAnd, BitOr, BitXor};
+
+/// A word holding one bit of each of `LANES` independent values.
+pub trait Lanes:
+    Copy + BitAnd<Output = Self> + BitOr<Output = Self> + BitXor<Output = Self>
+{
+    const LANES: usize;
+
+    fn zero() -> Self;
+
+    fn ones() -> Self;
+
+    fn get(&self, lane: usize) -> bool;
+
+    fn set(&mut self, lane: usize, v: bool);
+
+    /// Return the number of lanes whose bit is set.
+    fn count(&self) -> u32;
+
+    /// Return `ones()` if `v` else `zero()`.
+    fn splat(v: bool) -> Self {
+        if v {
+            Self::ones()
+        } else {
+            Self::zero()
+        }
+    }
+}
+
+macro_rules! impl_lanes_for_uint {
+    ($t:ty) => {
+        impl Lanes for $t {
+            const LANES: usize = 8 * std::mem::size_of::<$t>();
+
+            fn zero() -> Self {
+                0
+            }
+
+            fn ones() -> Self {
+                !0
+            }
+
+            fn get(&self, lane: usize) -> bool {
+                (self >> lane) & 1 == 1
+            }
+
+            fn set(&mut self, lane: usize, v: bool) {
+                *self = (*self & !(1 << lane)) | ((v as $t) << lane);
+            }
+
+            fn count(&self) -> u32 {
+                self.count_ones()
+            }
+        }
+    };
+}
+
+impl_lanes_for_uint!(u32);
+impl_lanes_for_uint!(u64);
+
+/// 256 lanes in 4 words.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct U256([u64; 4]);
+
+impl BitAnd for U256 {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        let mut ret = self;
+        for (v, r) in ret.0.iter_mut().zip(rhs.0.iter()) {
+            *v &= r;
+        }
+        ret
+    }
+}
+
+impl BitOr for U256 {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        let mut ret = self;
+        for (v, r) in ret.0.iter_mut().zip(rhs.0.iter()) {
+            *v |= r;
+        }
+        ret
+    }
+}
+
+impl BitXor for U256 {
+    type Output = Self;
+
+    fn bitxor(self, rhs: Self) -> Self {
+        let mut ret = self;
+        for (v, r) in ret.0.iter_mut().zip(rhs.0.iter()) {
+            *v ^= r;
+        }
+        ret
+    }
+}
+
+impl Lanes for U256 {
+    const LANES: usize = 256;
+
+    fn zero() -> Self {
+        U256([0; 4])
+    }
+
+    fn ones() -> Self {
+        U256([!0; 4])
+    }
+
+    fn get(&self, lane: usize) -> bool {
+        self.0[lane / 64].get(lane % 64)
+    }
+
+    fn set(&mut self, lane: usize, v: bool) {
+        self.0[lane / 64].set(lane % 64, v);
+    }
+
+    fn count(&self) -> u32 {
+        self.0.iter().map(|v| v.count_ones()).sum()
+    }
+}
+
+/// A bit-sliced 8-bit value per lane: `byte[i]` holds bit `i` of every lane.
+pub type SlicedByte<L> = [L; 8];
+
+/// RNGs of `L::LANES` independent states which transit in lock-step.
+///
+/// The states are bit-sliced, i.e. `bits[i]` holds bit `i` of every state, so
+/// a transition is a handful of word operations for all the lanes at once.
+/// The results are the same as `Rng` lane by lane.
+#[derive(Debug, Clone, Copy)]
+pub struct BatchRng<L: Lanes> {
+    bits: [L; 32],
+}
+
+pub type BatchRng32 = BatchRng<u32>;
+pub type BatchRng64 = BatchRng<u64>;
+pub type BatchRng256 = BatchRng<U256>;
+
+impl<L: Lanes> BatchRng<L> {
+    /// Create from exactly `L::LANES` states.
+    pub fn from_states(states: &[State]) -> Self {
+        assert_eq!(states.len(), L::LANES);
+
+        let mut bits = [L::zero(); 32];
+        for (lane, state) in states.iter().enumerate() {
+            let v: u32 = *state.as_ref();
+            for (i, b) in bits.iter_mut().enumerate() {
+                b.set(lane, (v >> i) & 1 == 1);
+            }
+        }
+        Self { bits }
+    }
+
+    /// Create from the `L::LANES` consecutive state values from `first`.
+    pub fn from_range(first: u32) -> Self {
+        let states: Vec<State> = (0..L::LANES as u32)
+            .map(|i| State::new(first.wrapping_add(i)))
+            .collect();
+        Self::from_states(&states)
+    }
+
+    pub fn state(&self, lane: usize) -> State {
+        let mut v = 0;
+        for (i, b) in self.bits.iter().enumerate() {
+            v |= (b.get(lane) as u32) << i;
+        }
+        State::new(v)
+    }
+
+    pub fn states(&self) -> Vec<State> {
+        (0..L::LANES).map(|lane| self.state(lane)).collect()
+    }
+
+    /// Transit the states of all lanes. See `State::next`.
+    pub fn transit(&mut self) {
+        let mut next = [L::zero(); 32];
+        for (j, n) in next.iter_mut().take(8).enumerate() {
+            *n = self.bits[5 + j] ^ self.bits[23 + j];
+        }
+        next[8..].copy_from_slice(&self.bits[..24]);
+        self.bits = next;
+    }
+
+    /// Bit-sliced version of `rand`.
+    pub fn rand_sliced(&mut self) -> SlicedByte<L> {
+        self.transit();
+        self.lowest_byte()
+    }
+
+    /// Bit-sliced version of `rand_by_multiply`.
+    pub fn rand_by_multiply_sliced(&mut self, upper: u8) -> SlicedByte<L> {
+        let rand = self.rand_sliced();
+        if upper == u8::MAX {
+            return rand;
+        }
+
+        // rand * (upper + 1) in 16 bits, by shift-and-add.
+        let factor = upper as u16 + 1;
+        let mut product = [L::zero(); 16];
+        for shift in 0..8 {
+            if (factor >> shift) & 1 == 1 {
+                let mut addend = [L::zero(); 16];
+                addend[shift..shift + 8].copy_from_slice(&rand);
+                product = add(&product, &addend);
+            }
+        }
+
+        let mut ret = [L::zero(); 8];
+        ret.copy_from_slice(&product[8..]);
+        ret
+    }
+
+    /// Bit-sliced version of `rand_multinomial`.
+    pub fn rand_multinomial_sliced(&mut self, offset: u8, mask: u8) -> SlicedByte<L> {
+        assert!(bit::is_powerof2(mask as u16 + 1));
+
+        let mut sum = splat_byte(offset);
+        for _ in 0..16 {
+            self.transit();
+            let mut rand = self.lowest_byte();
+            for (i, r) in rand.iter_mut().enumerate() {
+                if (mask >> i) & 1 == 0 {
+                    *r = L::zero();
+                }
+            }
+            sum = add(&sum, &rand);
+        }
+        sum
+    }
+
+    /// Return a `rand()` value of each lane. See `Rng::rand`.
+    pub fn rand(&mut self) -> Vec<u8> {
+        let sliced = self.rand_sliced();
+        unslice(&sliced)
+    }
+
+    /// Return a `rand_by_multiply(upper)` value of each lane. See `Rng::rand_by_multiply`.
+    pub fn rand_by_multiply(&mut self, upper: u8) -> Vec<u8> {
+        let sliced = self.rand_by_multiply_sliced(upper);
+        unslice(&sliced)
+    }
+
+    /// Return a `rand_multinomial(offset, mask)` value of each lane. See `Rng::rand_multinomial`.
+    pub fn rand_multinomial(&mut self, offset: u8, mask: u8) -> Vec<u8> {
+        let sliced = self.rand_multinomial_sliced(offset, mask);
+        unslice(&sliced)
+    }
+
+    fn lowest_byte(&self) -> SlicedByte<L> {
+        let mut ret = [L::zero(); 8];
+        ret.copy_from_slice(&self.bits[..8]);
+        ret
+    }
+}
+
+/// Return the lanes where the bit-sliced values equal `value`, as a lane mask.
+pub fn eq_byte<L: Lanes>(sliced: &SlicedByte<L>, value: u8) -> L {
+    let mut ret = L::ones();
+    for (i, b) in sliced.iter().enumerate() {
+        ret = ret & (*b ^ L::splat((value >> i) & 1 == 0));
+    }
+    ret
+}
+
+/// Return the value of each lane.
+pub fn unslice<L: Lanes>(sliced: &SlicedByte<L>) -> Vec<u8> {
+    (0..L::LANES)
+        .map(|lane| {
+            sliced
+                .iter()
+                .enumerate()
+                .fold(0, |acc, (i, b)| acc | ((b.get(lane) as u8) << i))
+        })
+        .collect()
+}
+
+fn splat_byte<L: Lanes>(value: u8) -> SlicedByte<L> {
+    let mut ret = [L::zero(); 8];
+    for (i, b) in ret.iter_mut().enumerate() {
+        *b = L::splat((value >> i) & 1 == 1);
+    }
+    ret
+}
+
+/// Wrapping addition of bit-sliced values by a ripple-carry adder.
+fn add<L: Lanes, const N: usize>(a: &[L; N], b: &[L; N]) -> [L; N] {
+    let mut ret = [L::zero(); N];
+    let mut carry = L::zero();
+    for i in 0..N {
+        let x = a[i] ^ b[i];
+        ret[i] = x ^ carry;
+        carry = (a[i] & b[i]) | (carry & x);
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rand::Rng;
+
+    fn test_same_as_rng<L: Lanes>() {
+        let states: Vec<State> = (0..L::LANES as u32)
+            .map(|i| State::new(i.wrapping_mul(0x9e3779b9) ^ 0xaae21259))
+            .collect();
+        let mut batch = BatchRng::<L>::from_states(&states);
+        let mut rngs: Vec<Rng> = states.iter().map(|s| Rng::from(*s)).collect();
+
+        assert_eq!(batch.states(), states);
+        for upper in [1, 16, 25, 127, 255].iter() {
+            let expected: Vec<u8> = rngs
+                .iter_mut()
+                .map(|r| r.rand_by_multiply(*upper))
+                .collect();
+            assert_eq!(batch.rand_by_multiply(*upper), expected);
+        }
+        for (offset, mask) in [(6, 0xf), (136, 0x1f), (200, 0xff)].iter() {
+            let expected: Vec<u8> = rngs
+                .iter_mut()
+                .map(|r| r.rand_multinomial(*offset, *mask))
+                .collect();
+            assert_eq!(batch.rand_multinomial(*offset, *mask), expected);
+        }
+        let expected: Vec<u8> = rngs.iter_mut().map(|r| r.rand()).collect();
+        assert_eq!(batch.rand(), expected);
+        assert_eq!(
+            batch.states(),
+            rngs.iter().map(|r| r.state()).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_batch32() {
+        test_same_as_rng::<u32>();
+    }
+
+    #[test]
+    fn test_batch64() {
+        test_same_as_rng::<u64>();
+    }
+
+    #[test]
+    fn test_batch256() {
+        test_same_as_rng::<U256>();
+    }
+
+    #[test]
+    fn test_eq_byte() {
+        let mut batch = BatchRng32::from_range(0x12345600);
+        let sliced = batch.rand_sliced();
+        let values = unslice(&sliced);
+
+        let matched = eq_byte(&sliced, values[3]);
+        for (lane, v) in values.iter().enumerate() {
+            assert_eq!(matched.get(lane), *v == values[3]);
+        }
+        assert_eq!(
+            matched.count() as usize,
+            values.iter().filter(|v| **v == values[3]).count()
+        );
+    }
+}
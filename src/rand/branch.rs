@@ -0,0 +1,280 @@
+use crate::rand::trace::{TraceEntry, TracingRng};
+use crate::rand::{Rng, State};
+
+use std::collections::HashMap;
+
+/// A named snapshot of the RNG in a `BranchTimeline`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub name: String,
+    pub state: State,
+    /// The number of transitions from the parent snapshot, or 0 for the root.
+    pub transitions: u64,
+    /// The calls to the RNG from the parent snapshot, if recorded by `branch`.
+    pub entries: Vec<TraceEntry>,
+    parent: Option<usize>,
+}
+
+/// The difference between two snapshots in a `BranchTimeline`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BranchDiff<'a> {
+    /// The latest snapshot which both branches go through.
+    pub ancestor: &'a Snapshot,
+    /// The snapshots from `ancestor` (exclusive) to the left one (inclusive).
+    pub left: Vec<&'a Snapshot>,
+    /// The snapshots from `ancestor` (exclusive) to the right one (inclusive).
+    pub right: Vec<&'a Snapshot>,
+}
+
+impl BranchDiff<'_> {
+    /// Return the number of transitions from the ancestor to the left one.
+    pub fn left_transitions(&self) -> u64 {
+        self.left.iter().map(|s| s.transitions).sum()
+    }
+
+    /// Return the number of transitions from the ancestor to the right one.
+    pub fn right_transitions(&self) -> u64 {
+        self.right.iter().map(|s| s.transitions).sum()
+    }
+
+    /// Return how many more transitions the right branch consumed than the left one.
+    pub fn offset(&self) -> i64 {
+        self.right_transitions() as i64 - self.left_transitions() as i64
+    }
+
+    /// Return whether both branches end in the same state.
+    pub fn is_same_state(&self) -> bool {
+        let last = |branch: &[&Snapshot]| branch.last().map_or(self.ancestor.state, |s| s.state);
+        last(&self.left) == last(&self.right)
+    }
+}
+
+/// A tree of named RNG snapshots for "what if" analysis.
+///
+/// Every snapshot but the root branches from a parent snapshot after some
+/// transitions, so alternative action sequences from the same point can be
+/// explored side by side, compared by `diff`, and replayed by `replay`.
+/// Unlike `FrameTimeline`, this does not model frames.
+#[derive(Debug, Clone)]
+pub struct BranchTimeline {
+    snapshots: Vec<Snapshot>,
+    names: HashMap<String, usize>,
+}
+
+impl BranchTimeline {
+    /// Create a timeline with the root snapshot `name` at `state`.
+    pub fn new(name: &str, state: State) -> Self {
+        let mut timeline = Self {
+            snapshots: vec![],
+            names: HashMap::new(),
+        };
+        timeline.push(name, state, 0, vec![], None);
+        timeline
+    }
+
+    pub fn root(&self) -> &Snapshot {
+        &self.snapshots[0]
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Snapshot> {
+        self.names.get(name).map(|i| &self.snapshots[*i])
+    }
+
+    pub fn parent(&self, name: &str) -> Option<&Snapshot> {
+        self.snapshots[self.index(name)]
+            .parent
+            .map(|i| &self.snapshots[i])
+    }
+
+    /// Return all snapshots in the order of creation.
+    pub fn snapshots(&self) -> &[Snapshot] {
+        &self.snapshots
+    }
+
+    /// Run `action` on a RNG from the snapshot `from`, and save the result
+    /// as a new snapshot `name` with the calls it made.
+    ///
+    /// Panics if `from` does not exist or `name` already exists.
+    pub fn branch<T, F: FnOnce(&mut TracingRng) -> T>(
+        &mut self,
+        from: &str,
+        name: &str,
+        action: F,
+    ) -> T {
+        let parent = self.index(from);
+        let mut rng = TracingRng::new(Rng::from(self.snapshots[parent].state));
+        let ret = action(&mut rng);
+
+        let transitions = rng
+            .entries()
+            .iter()
+            .map(|e| e.call.transitions() as u64)
+            .sum();
+        self.push(
+            name,
+            rng.state(),
+            transitions,
+            rng.entries().to_vec(),
+            Some(parent),
+        );
+        ret
+    }
+
+    /// Save a new snapshot `name` after `transitions` transitions from the
+    /// snapshot `from`, e.g. after waiting some frames.
+    ///
+    /// Panics if `from` does not exist or `name` already exists.
+    pub fn advance(&mut self, from: &str, name: &str, transitions: u64) -> &Snapshot {
+        let parent = self.index(from);
+        let state = self.snapshots[parent].state.advance(transitions);
+        self.push(name, state, transitions, vec![], Some(parent));
+        self.snapshots.last().unwrap()
+    }
+
+    /// Return the snapshots from the root to `name`, both inclusive.
+    pub fn path(&self, name: &str) -> Vec<&Snapshot> {
+        let mut path = vec![];
+        let mut i = Some(self.index(name));
+        while let Some(j) = i {
+            path.push(&self.snapshots[j]);
+            i = self.snapshots[j].parent;
+        }
+        path.reverse();
+        path
+    }
+
+    /// Return the number of transitions from the root to `name`.
+    pub fn transitions(&self, name: &str) -> u64 {
+        self.path(name).iter().map(|s| s.transitions).sum()
+    }
+
+    /// Return a RNG at the snapshot `name`, replayed from the root state.
+    pub fn replay(&self, name: &str) -> Rng {
+        let mut rng = Rng::from(self.root().state);
+        rng.skip(self.transitions(name));
+        debug_assert_eq!(rng.state(), self.snapshots[self.index(name)].state);
+        rng
+    }
+
+    /// Compare the branches leading to `left` and `right`.
+    pub fn diff(&self, left: &str, right: &str) -> BranchDiff<'_> {
+        let left = self.path(left);
+        let right = self.path(right);
+        let common = left
+            .iter()
+            .zip(right.iter())
+            .take_while(|(l, r)| l.name == r.name)
+            .count();
+
+        BranchDiff {
+            ancestor: left[common - 1],
+            left: left[common..].to_vec(),
+            right: right[common..].to_vec(),
+        }
+    }
+
+    fn index(&self, name: &str) -> usize {
+        match self.names.get(name) {
+            Some(i) => *i,
+            None => panic!("no snapshot named {:?}", name),
+        }
+    }
+
+    fn push(
+        &mut self,
+        name: &str,
+        state: State,
+        transitions: u64,
+        entries: Vec<TraceEntry>,
+        parent: Option<usize>,
+    ) {
+        assert!(
+            !self.names.contains_key(name),
+            "snapshot {:?} already exists",
+            name
+        );
+
+        self.names.insert(name.to_string(), self.snapshots.len());
+        self.snapshots.push(Snapshot {
+            name: name.to_string(),
+            state,
+            transitions,
+            entries,
+            parent,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::battle::{self, Character};
+
+    #[test]
+    fn test_branch_and_replay() {
+        let mut timeline = BranchTimeline::new("start", State::default());
+
+        let value = timeline.branch("start", "rand", |rng| rng.rand());
+        timeline.branch("rand", "multinomial", |rng| {
+            rng.rand_multinomial(6, 0xf);
+        });
+        timeline.advance("start", "wait", 3);
+
+        assert_eq!(value, Rng::default().rand());
+        assert_eq!(timeline.get("rand").unwrap().transitions, 1);
+        assert_eq!(timeline.get("rand").unwrap().entries.len(), 1);
+        assert_eq!(timeline.transitions("multinomial"), 17);
+        assert_eq!(
+            timeline.get("multinomial").unwrap().state,
+            State::default().advance(17)
+        );
+        assert_eq!(timeline.parent("multinomial").unwrap().name, "rand");
+        assert_eq!(timeline.parent("start"), None);
+        assert_eq!(
+            timeline
+                .path("multinomial")
+                .iter()
+                .map(|s| s.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["start", "rand", "multinomial"]
+        );
+
+        let mut rng = timeline.replay("wait");
+        assert_eq!(rng.state(), State::default().advance(3));
+        assert_eq!(rng.rand(), State::default().advance(4).rand());
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_diff() {
+        let mut timeline = BranchTimeline::new("start", State::new(0x12345678));
+        timeline.advance("start", "encounter", 5);
+        timeline.branch("encounter", "attack", |rng| {
+            battle::physical_damage(rng, 100, 50, false, Character::Player)
+        });
+        timeline.branch("encounter", "defend", |rng| rng.rand());
+        timeline.branch("defend", "attack after defend", |rng| {
+            battle::physical_damage(rng, 100, 50, false, Character::Player)
+        });
+
+        let diff = timeline.diff("attack", "attack after defend");
+        assert_eq!(diff.ancestor.name, "encounter");
+        assert_eq!(diff.left.len(), 1);
+        assert_eq!(diff.right.len(), 2);
+        assert_eq!(diff.offset(), 1);
+        assert!(!diff.is_same_state());
+        assert_eq!(diff.left[0].entries[0].tag, "physical_damage");
+
+        let diff = timeline.diff("encounter", "encounter");
+        assert_eq!(diff.ancestor.name, "encounter");
+        assert!(diff.left.is_empty() && diff.right.is_empty());
+        assert!(diff.is_same_state());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_duplicate_name() {
+        let mut timeline = BranchTimeline::new("start", State::default());
+        timeline.advance("start", "start", 1);
+    }
+}
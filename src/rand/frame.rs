@@ -0,0 +1,107 @@
+use crate::rand::{Rng, State};
+
+use std::ops::RangeInclusive;
+
+/// A RNG on which frame advances and actions are interleaved.
+///
+/// The state transits both once every frame (SR at 0x0006a1) and whenever an
+/// action consumes random numbers (e.g. SR at 0x0012e3). This keeps the frame
+/// count so that "wait k frames, then act" can be modeled.
+#[derive(Debug, Clone, Default)]
+pub struct FrameTimeline {
+    rng: Rng,
+    frame: u64,
+}
+
+impl FrameTimeline {
+    pub fn new(rng: Rng) -> Self {
+        Self { rng, frame: 0 }
+    }
+
+    pub fn rng(&self) -> &Rng {
+        &self.rng
+    }
+
+    pub fn state(&self) -> State {
+        self.rng.state()
+    }
+
+    /// Return the number of frames elapsed since the beginning.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Wait `frames` frames without any action.
+    pub fn wait(&mut self, frames: u64) -> &mut Self {
+        self.rng.advance_frames(frames);
+        self.frame += frames;
+        self
+    }
+
+    /// Run an action in the current frame and return its result.
+    ///
+    /// The action consumes random numbers from the RNG, but does not advance
+    /// the frame.
+    pub fn act<T, F: FnOnce(&mut Rng) -> T>(&mut self, action: F) -> T {
+        action(&mut self.rng)
+    }
+
+    /// Return the results of the action after waiting each number of frames
+    /// in `delays`, without changing this timeline.
+    pub fn scan_delays<T, F: Fn(&mut Rng) -> T>(
+        &self,
+        delays: RangeInclusive<u64>,
+        action: F,
+    ) -> Vec<(u64, T)> {
+        let mut timeline = self.clone();
+        timeline.wait(*delays.start());
+
+        let mut results = Vec::new();
+        for delay in delays {
+            let mut branch = timeline.clone();
+            results.push((delay, branch.act(&action)));
+            timeline.wait(1);
+        }
+        results
+    }
+}
+
+impl From<Rng> for FrameTimeline {
+    fn from(rng: Rng) -> Self {
+        Self::new(rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wait_and_act() {
+        let mut timeline = FrameTimeline::default();
+
+        let rand = timeline.wait(3).act(|rng| rng.rand());
+
+        let mut rng = Rng::default();
+        rng.advance_frames(3);
+        assert_eq!(rand, rng.rand());
+        assert_eq!(timeline.frame(), 3);
+        assert_eq!(timeline.state(), rng.state());
+    }
+
+    #[test]
+    fn test_scan_delays() {
+        let timeline = FrameTimeline::default();
+
+        let results = timeline.scan_delays(2..=5, |rng| rng.rand());
+
+        assert_eq!(results.len(), 4);
+        for (delay, rand) in results {
+            let mut rng = Rng::default();
+            rng.advance_frames(delay);
+            assert_eq!(rand, rng.rand());
+        }
+        assert_eq!(timeline.frame(), 0);
+        assert_eq!(timeline.state(), State::default());
+    }
+}
@@ -0,0 +1,131 @@
+/// A 32x32 matrix over GF(2), stored as its column vectors.
+///
+/// `cols[i]` is the image of the unit vector `1 << i`, so applying the matrix
+/// to a vector is the XOR of the columns selected by its set bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Matrix {
+    cols: [u32; 32],
+}
+
+impl Matrix {
+    pub fn identity() -> Self {
+        Self::from_linear_map(|v| v)
+    }
+
+    /// Build the matrix of a linear map by sampling it on the unit vectors.
+    pub fn from_linear_map<F: Fn(u32) -> u32>(f: F) -> Self {
+        let mut cols = [0; 32];
+        for (i, col) in cols.iter_mut().enumerate() {
+            *col = f(1 << i);
+        }
+        Self { cols }
+    }
+
+    pub fn apply(&self, v: u32) -> u32 {
+        let mut ret = 0;
+        let mut rest = v;
+        while rest != 0 {
+            let i = rest.trailing_zeros() as usize;
+            ret ^= self.cols[i];
+            rest &= rest - 1;
+        }
+        ret
+    }
+
+    /// Return the composition `self ∘ rhs`, i.e. `rhs` is applied first.
+    pub fn mul(&self, rhs: &Matrix) -> Matrix {
+        let mut cols = [0; 32];
+        for (col, rhs_col) in cols.iter_mut().zip(rhs.cols.iter()) {
+            *col = self.apply(*rhs_col);
+        }
+        Matrix { cols }
+    }
+
+    /// Return the sum, i.e. the element-wise XOR.
+    pub fn add(&self, rhs: &Matrix) -> Matrix {
+        let mut cols = self.cols;
+        for (col, rhs_col) in cols.iter_mut().zip(rhs.cols.iter()) {
+            *col ^= *rhs_col;
+        }
+        Matrix { cols }
+    }
+
+    pub fn pow(&self, n: u64) -> Matrix {
+        let mut ret = Matrix::identity();
+        let mut base = *self;
+        let mut rest = n;
+        while rest != 0 {
+            if rest & 1 == 1 {
+                ret = base.mul(&ret);
+            }
+            base = base.mul(&base);
+            rest >>= 1;
+        }
+        ret
+    }
+
+    /// Return the dimension of the image.
+    pub fn rank(&self) -> u32 {
+        // `basis[i]` holds a reduced vector whose highest set bit is `i`, if any.
+        let mut basis = [0_u32; 32];
+        let mut rank = 0;
+        for col in self.cols.iter() {
+            let mut v = *col;
+            while v != 0 {
+                let top = 31 - v.leading_zeros() as usize;
+                if basis[top] == 0 {
+                    basis[top] = v;
+                    rank += 1;
+                    break;
+                }
+                v ^= basis[top];
+            }
+        }
+        rank
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let rotate = Matrix::from_linear_map(|v| v.rotate_left(3));
+
+        assert_eq!(rotate.apply(0), 0);
+        assert_eq!(rotate.apply(0x12345678), 0x12345678_u32.rotate_left(3));
+    }
+
+    #[test]
+    fn test_mul() {
+        let rotate = Matrix::from_linear_map(|v| v.rotate_left(3));
+        let shift = Matrix::from_linear_map(|v| v << 1);
+
+        assert_eq!(
+            shift.mul(&rotate).apply(0x92345678),
+            0x92345678_u32.rotate_left(3) << 1
+        );
+    }
+
+    #[test]
+    fn test_pow() {
+        let rotate = Matrix::from_linear_map(|v| v.rotate_left(3));
+
+        assert_eq!(rotate.pow(0), Matrix::identity());
+        assert_eq!(
+            rotate.pow(5).apply(0x12345678),
+            0x12345678_u32.rotate_left(15)
+        );
+        assert_eq!(rotate.pow(32), Matrix::identity());
+    }
+
+    #[test]
+    fn test_rank() {
+        let shift = Matrix::from_linear_map(|v| v << 4);
+
+        assert_eq!(Matrix::identity().rank(), 32);
+        assert_eq!(shift.rank(), 28);
+        assert_eq!(Matrix::identity().add(&Matrix::identity()).rank(), 0);
+    }
+}
@@ -0,0 +1,120 @@
+use crate::bit;
+use crate::rand::{Rng, State};
+
+/// Return the probability mass function of `Rng::rand_by_multiply(upper)`,
+/// indexed by the value in [0, upper], assuming `rand()` is uniform.
+pub fn rand_by_multiply(upper: u8) -> Vec<f64> {
+    let mut pmf = vec![0.0; upper as usize + 1];
+    for v in 0..=255 {
+        pmf[State::new(v).rand_by_multiply(upper) as usize] += 1.0 / 256.0;
+    }
+    pmf
+}
+
+/// Return the probability mass function of `Rng::rand_multinomial(offset, mask)`,
+/// indexed by the value in [0, 255], assuming `rand()` values are uniform and
+/// independent.
+///
+/// The sum of 16 values in [0, mask] may exceed 255, in which case it wraps
+/// around as in the game.
+pub fn rand_multinomial(offset: u8, mask: u8) -> Vec<f64> {
+    assert!(bit::is_powerof2(mask as u16 + 1));
+
+    // The distribution of the sum before wrapping around.
+    let term = 1.0 / (mask as f64 + 1.0);
+    let mut sum = vec![1.0];
+    for _ in 0..16 {
+        let mut next = vec![0.0; sum.len() + mask as usize];
+        for (s, p) in sum.iter().enumerate() {
+            for v in 0..=mask as usize {
+                next[s + v] += p * term;
+            }
+        }
+        sum = next;
+    }
+
+    let mut pmf = vec![0.0; 256];
+    for (s, p) in sum.iter().enumerate() {
+        pmf[(offset as usize + s) % 256] += p;
+    }
+    pmf
+}
+
+/// Return the empirical probability mass function of `f`, indexed by the value
+/// in [0, 255], over `samples` calls starting at successive states from `state`.
+///
+/// The i-th call starts at the state after i transitions from `state`, so
+/// taking the period of the orbit as `samples` gives the exact distribution
+/// under the actual generator.
+pub fn empirical<F: Fn(&mut Rng) -> u8>(state: State, samples: usize, f: F) -> Vec<f64> {
+    let mut counts = vec![0_u64; 256];
+    let mut start = state;
+    for _ in 0..samples {
+        counts[f(&mut Rng::from(start)) as usize] += 1;
+        start.transit();
+    }
+
+    counts.iter().map(|c| *c as f64 / samples as f64).collect()
+}
+
+/// Return the empirical counterpart of `rand_by_multiply`, see `empirical`.
+pub fn empirical_rand_by_multiply(state: State, samples: usize, upper: u8) -> Vec<f64> {
+    let mut pmf = empirical(state, samples, |rng| rng.rand_by_multiply(upper));
+    pmf.truncate(upper as usize + 1);
+    pmf
+}
+
+/// Return the empirical counterpart of `rand_multinomial`, see `empirical`.
+pub fn empirical_rand_multinomial(state: State, samples: usize, offset: u8, mask: u8) -> Vec<f64> {
+    empirical(state, samples, |rng| rng.rand_multinomial(offset, mask))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::assert_close;
+
+    #[test]
+    fn test_rand_by_multiply() {
+        let pmf = rand_by_multiply(16);
+
+        assert_eq!(pmf.len(), 17);
+        assert_close(pmf.iter().sum(), 1.0, 1e-12);
+        // rand() * 17 / 256 == 0 for rand() in [0, 15].
+        assert_close(pmf[0], 16.0 / 256.0, 1e-12);
+        assert_eq!(rand_by_multiply(255), vec![1.0 / 256.0; 256]);
+    }
+
+    #[test]
+    fn test_rand_multinomial() {
+        let pmf = rand_multinomial(136, 31);
+        assert_eq!(pmf.len(), 256);
+        assert_close(pmf.iter().sum(), 1.0, 1e-12);
+
+        // offset + Binomial(16, 1/2), wrapping around 256.
+        let pmf = rand_multinomial(250, 1);
+        let mut binomial = 1.0;
+        for k in 0..=16 {
+            assert_close(pmf[(250 + k) % 256], binomial / 65536.0, 1e-12);
+            binomial = binomial * (16 - k) as f64 / (k + 1) as f64;
+        }
+    }
+
+    #[test]
+    fn test_empirical() {
+        let state = State::default();
+
+        let pmf = rand_by_multiply(16);
+        let empirical = empirical_rand_by_multiply(state, 100000, 16);
+        assert_eq!(empirical.len(), 17);
+        for (p, q) in pmf.iter().zip(empirical.iter()) {
+            assert_close(*p, *q, 0.005);
+        }
+
+        let pmf = rand_multinomial(6, 0xf);
+        let empirical = empirical_rand_multinomial(state, 100000, 6, 0xf);
+        for (p, q) in pmf.iter().zip(empirical.iter()) {
+            assert_close(*p, *q, 0.005);
+        }
+    }
+}
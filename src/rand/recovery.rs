@@ -0,0 +1,294 @@
+use crate::rand::search::{Search, STATES};
+use crate::rand::{pmf, Rng, State};
+
+/// An observed result of one of the RNG subroutines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Observation {
+    /// A result of `Rng::rand`, i.e. the subroutine at 0x0012e3.
+    Rand(u8),
+    /// A result of `Rng::rand_by_multiply`, i.e. the subroutine at 0x00133e.
+    RandByMultiply { upper: u8, value: u8 },
+    /// A result of `Rng::rand_multinomial`, i.e. the subroutine at 0x0014d4.
+    RandMultinomial { offset: u8, mask: u8, value: u8 },
+}
+
+impl Observation {
+    /// Return the address of the subroutine which produced this observation.
+    pub fn subroutine(&self) -> u32 {
+        match self {
+            Observation::Rand(_) => 0x0012e3,
+            Observation::RandByMultiply { .. } => 0x00133e,
+            Observation::RandMultinomial { .. } => 0x0014d4,
+        }
+    }
+
+    /// Return the number of transitions consumed by the subroutine.
+    pub fn transitions(&self) -> usize {
+        match self {
+            Observation::Rand(_) | Observation::RandByMultiply { .. } => 1,
+            Observation::RandMultinomial { .. } => 16,
+        }
+    }
+
+    /// Return the probability that a random state reproduces this observation.
+    pub fn probability(&self) -> f64 {
+        match *self {
+            Observation::Rand(_) => 1.0 / 256.0,
+            Observation::RandByMultiply { upper, value } => pmf::rand_by_multiply(upper)
+                .get(value as usize)
+                .copied()
+                .unwrap_or(0.0),
+            Observation::RandMultinomial {
+                offset,
+                mask,
+                value,
+            } => pmf::rand_multinomial(offset, mask)[value as usize],
+        }
+    }
+
+    /// Return the lowest bytes of the states which are allowed at each transition.
+    fn byte_candidates(&self) -> Vec<Vec<u8>> {
+        match *self {
+            Observation::Rand(value) => vec![vec![value]],
+            Observation::RandByMultiply { upper, value } => vec![(0..=255)
+                .filter(|v| State::new(*v as u32).rand_by_multiply(upper) == value)
+                .collect()],
+            Observation::RandMultinomial { .. } => vec![(0..=255).collect(); 16],
+        }
+    }
+
+    /// Consume the RNG like the subroutine and return whether the result matches.
+    fn matches(&self, rng: &mut Rng) -> bool {
+        match *self {
+            Observation::Rand(value) => rng.rand() == value,
+            Observation::RandByMultiply { upper, value } => rng.rand_by_multiply(upper) == value,
+            Observation::RandMultinomial {
+                offset,
+                mask,
+                value,
+            } => rng.rand_multinomial(offset, mask) == value,
+        }
+    }
+}
+
+/// Return whether the observations are reproduced from the state, in order.
+pub fn is_consistent(state: State, observations: &[Observation]) -> bool {
+    let mut rng = Rng::from(state);
+    observations.iter().all(|o| o.matches(&mut rng))
+}
+
+/// The bit of a state which never affects the results.
+const UNUSED_BIT: u32 = 1 << 31;
+
+/// The most states `recover_states` expects to return, as many as a single
+/// `rand()` result leaves.
+pub const MAX_EXPECTED_STATES: u64 = 1 << 24;
+
+/// Return all states from which the observations are reproduced, in ascending order.
+///
+/// After 4 transitions the state consists of the 4 lowest bytes observed on the
+/// way, and any 4 consecutive lowest bytes determine the whole sequence in both
+/// directions. So this enumerates only the 4 consecutive transitions which are
+/// the most constrained by the observations, and checks each candidate against
+/// all of them.
+///
+/// Bit 31 of the initial state never affects the results, so the states are
+/// returned in pairs differing only in that bit.
+///
+/// A result of `rand_multinomial` constrains only the sum of its 16 bytes, not
+/// any byte by itself. When no 4 consecutive transitions have fewer than 2^31
+/// candidates, e.g. the observations are only level-ups, this falls back to a
+/// parallel search over the 2^31 states with bit 31 cleared, which takes about
+/// a minute of CPU time in a release build. See `recover_states_by_search`.
+///
+/// Return `None` without enumerating anything if the observations are expected
+/// to be reproduced from more than `MAX_EXPECTED_STATES` states, e.g. there are
+/// none, since collecting them would take up to 2^32 states of memory.
+pub fn recover_states(observations: &[Observation]) -> Option<Vec<State>> {
+    let expected = observations
+        .iter()
+        .map(|o| o.probability())
+        .product::<f64>()
+        * STATES as f64;
+    if expected > MAX_EXPECTED_STATES as f64 {
+        return None;
+    }
+
+    let mut candidates: Vec<Vec<u8>> = observations
+        .iter()
+        .flat_map(|o| o.byte_candidates())
+        .collect();
+    while candidates.len() < 4 {
+        candidates.push((0..=255).collect());
+    }
+
+    let (window, count) = (0..=candidates.len() - 4)
+        .map(|i| {
+            let count = candidates[i..i + 4]
+                .iter()
+                .map(|c| c.len() as u64)
+                .product::<u64>();
+            (i, count)
+        })
+        .min_by_key(|(_, count)| *count)
+        .unwrap();
+    if count >= STATES / 2 {
+        return Some(recover_states_by_search(
+            observations,
+            &Search::new().range(0..STATES / 2),
+        ));
+    }
+
+    let mut states = Vec::new();
+    for &b0 in &candidates[window] {
+        for &b1 in &candidates[window + 1] {
+            for &b2 in &candidates[window + 2] {
+                for &b3 in &candidates[window + 3] {
+                    let v = u32::from_be_bytes([b0, b1, b2, b3]);
+
+                    // The state after `window + 4` transitions, rewound to the first one.
+                    let first = match State::new(v).rewind(window as u64 + 3) {
+                        Some(first) => first,
+                        None => continue,
+                    };
+
+                    let inits = first.prev();
+                    if is_consistent(inits[0], observations) {
+                        states.extend(inits);
+                    }
+                }
+            }
+        }
+    }
+
+    states.sort();
+    Some(states)
+}
+
+/// Return all states from which the observations are reproduced, checking each
+/// state in the range of `search` by brute force, in ascending order.
+///
+/// Like `recover_states`, each match is returned with its pair differing only
+/// in bit 31, whether or not the pair is in the range.
+pub fn recover_states_by_search(observations: &[Observation], search: &Search) -> Vec<State> {
+    let mut states: Vec<State> = search
+        .collect(|rng| observations.iter().all(|o| o.matches(rng)))
+        .into_iter()
+        .flat_map(|s| {
+            let v = *s.as_ref() & !UNUSED_BIT;
+            vec![State::new(v), State::new(v | UNUSED_BIT)]
+        })
+        .collect();
+    states.sort();
+    states.dedup();
+    states
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subroutine() {
+        assert_eq!(Observation::Rand(0).subroutine(), 0x0012e3);
+        assert_eq!(
+            Observation::RandByMultiply {
+                upper: 16,
+                value: 0
+            }
+            .subroutine(),
+            0x00133e
+        );
+        assert_eq!(
+            Observation::RandMultinomial {
+                offset: 136,
+                mask: 31,
+                value: 0
+            }
+            .subroutine(),
+            0x0014d4
+        );
+    }
+
+    #[test]
+    fn test_recover_from_rand() {
+        let mut rng = Rng::default();
+        let observations: Vec<_> = (0..4).map(|_| Observation::Rand(rng.rand())).collect();
+
+        let states = recover_states(&observations).unwrap();
+
+        assert_eq!(states, vec![State::new(0x2ae21259), State::default()]);
+    }
+
+    #[test]
+    fn test_recover_from_mixed() {
+        let init = State::new(0x12345678);
+        let mut rng = Rng::from(init);
+        let mut observations = Vec::new();
+        for _ in 0..3 {
+            observations.push(Observation::RandByMultiply {
+                upper: 16,
+                value: rng.rand_by_multiply(16),
+            });
+        }
+        observations.push(Observation::RandMultinomial {
+            offset: 136,
+            mask: 31,
+            value: rng.rand_multinomial(136, 31),
+        });
+        observations.push(Observation::Rand(rng.rand()));
+        observations.push(Observation::Rand(rng.rand()));
+
+        let states = recover_states(&observations).unwrap();
+
+        assert!(states.contains(&init));
+        assert!(states.iter().all(|s| is_consistent(*s, &observations)));
+    }
+
+    #[test]
+    fn test_recover_from_multinomial_by_search() {
+        let init = State::new(0x80012345);
+        let mut rng = Rng::from(init);
+        let observations: Vec<_> = (0..3)
+            .map(|_| Observation::RandMultinomial {
+                offset: 136,
+                mask: 31,
+                value: rng.rand_multinomial(136, 31),
+            })
+            .collect();
+
+        let search = Search::new().range(0x10000..0x20000);
+        let states = recover_states_by_search(&observations, &search);
+
+        assert!(states.contains(&init));
+        assert!(states.contains(&State::new(0x00012345)));
+        assert!(states.iter().all(|s| is_consistent(*s, &observations)));
+    }
+
+    #[test]
+    fn test_recover_inconsistent() {
+        let mut rng = Rng::default();
+        let mut observations: Vec<_> = (0..5).map(|_| Observation::Rand(rng.rand())).collect();
+        observations.push(Observation::Rand(rng.rand().wrapping_add(1)));
+
+        assert_eq!(recover_states(&observations), Some(vec![]));
+    }
+
+    #[test]
+    fn test_recover_unconstrained() {
+        assert_eq!(recover_states(&[]), None);
+
+        // Each matches half the states.
+        let observations = vec![Observation::RandByMultiply { upper: 1, value: 0 }; 4];
+        assert_eq!(recover_states(&observations), None);
+
+        // Always matches without constraining any byte.
+        let observation = Observation::RandMultinomial {
+            offset: 136,
+            mask: 0,
+            value: 136,
+        };
+        assert_eq!(observation.probability(), 1.0);
+        assert_eq!(recover_states(&[observation; 8]), None);
+    }
+}
@@ -0,0 +1,212 @@
+use crate::rand::{Rng, State};
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+/// The number of all states.
+pub const STATES: u64 = 1 << 32;
+
+/// A point to resume a search from.
+///
+/// Every state before `next` has been checked and its matches have been
+/// reported, so resuming from here reports no match twice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Checkpoint {
+    pub next: u64,
+}
+
+/// The progress of a search, reported after each chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// The number of states checked in this run.
+    pub checked: u64,
+    /// The number of states to check in this run.
+    pub total: u64,
+    /// The number of matches reported in this run.
+    pub matches: u64,
+    pub checkpoint: Checkpoint,
+}
+
+/// A brute-force search over the state space, split across threads.
+///
+/// The states are split into chunks, which the threads take in turn. Matches
+/// are reported in ascending order of state.
+#[derive(Debug, Clone)]
+pub struct Search {
+    range: Range<u64>,
+    threads: usize,
+    chunk_size: u64,
+}
+
+impl Default for Search {
+    fn default() -> Self {
+        Self {
+            range: 0..STATES,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            chunk_size: 1 << 20,
+        }
+    }
+}
+
+impl Search {
+    /// Search the whole state space with all available threads.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Search only the state values in `range`.
+    pub fn range(mut self, range: Range<u64>) -> Self {
+        assert!(range.end <= STATES);
+        self.range = range;
+        self
+    }
+
+    pub fn threads(mut self, threads: usize) -> Self {
+        assert!(threads > 0);
+        self.threads = threads;
+        self
+    }
+
+    pub fn chunk_size(mut self, chunk_size: u64) -> Self {
+        assert!(chunk_size > 0);
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Skip the states before the checkpoint of a previous run.
+    pub fn resume(mut self, checkpoint: Checkpoint) -> Self {
+        self.range.start = u64::max(self.range.start, checkpoint.next);
+        self
+    }
+
+    /// Run `predicate` on a RNG from each state, and call `on_match` with each
+    /// state for which it returns true. `on_progress` is called after each
+    /// chunk. Return the checkpoint at the end.
+    pub fn run<P, M, G>(&self, predicate: P, mut on_match: M, mut on_progress: G) -> Checkpoint
+    where
+        P: Fn(&mut Rng) -> bool + Sync,
+        M: FnMut(State),
+        G: FnMut(&Progress),
+    {
+        let start = self.range.start;
+        let end = u64::max(start, self.range.end);
+        let chunks = (end - start).div_ceil(self.chunk_size);
+        let next_chunk = AtomicU64::new(0);
+        let (tx, rx) = mpsc::channel();
+
+        thread::scope(|scope| {
+            for _ in 0..self.threads {
+                let tx = tx.clone();
+                let next_chunk = &next_chunk;
+                let predicate = &predicate;
+                scope.spawn(move || loop {
+                    let chunk = next_chunk.fetch_add(1, Ordering::Relaxed);
+                    if chunk >= chunks {
+                        break;
+                    }
+
+                    let begin = start + chunk * self.chunk_size;
+                    let matches: Vec<State> = (begin..u64::min(begin + self.chunk_size, end))
+                        .map(|v| State::new(v as u32))
+                        .filter(|state| predicate(&mut Rng::from(*state)))
+                        .collect();
+                    if tx.send((chunk, matches)).is_err() {
+                        break;
+                    }
+                });
+            }
+            drop(tx);
+
+            // Report the chunks in order, holding the ones finished early.
+            let mut pending = BTreeMap::new();
+            let mut progress = Progress {
+                checked: 0,
+                total: end - start,
+                matches: 0,
+                checkpoint: Checkpoint { next: start },
+            };
+            for (chunk, matches) in rx {
+                pending.insert(chunk, matches);
+
+                let mut reported = false;
+                while let Some(matches) =
+                    pending.remove(&((progress.checkpoint.next - start) / self.chunk_size))
+                {
+                    progress.matches += matches.len() as u64;
+                    matches.into_iter().for_each(&mut on_match);
+
+                    let next = u64::min(progress.checkpoint.next + self.chunk_size, end);
+                    progress.checked += next - progress.checkpoint.next;
+                    progress.checkpoint.next = next;
+                    reported = true;
+                }
+                if reported {
+                    on_progress(&progress);
+                }
+            }
+
+            progress.checkpoint
+        })
+    }
+
+    /// Return all states for which `predicate` returns true, in ascending order.
+    pub fn collect<P>(&self, predicate: P) -> Vec<State>
+    where
+        P: Fn(&mut Rng) -> bool + Sync,
+    {
+        let mut states = Vec::new();
+        self.run(predicate, |state| states.push(state), |_| {});
+        states
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sequential(range: Range<u64>) -> Vec<State> {
+        range
+            .map(|v| State::new(v as u32))
+            .filter(|state| Rng::from(*state).rand() == 0)
+            .collect()
+    }
+
+    #[test]
+    fn test_collect() {
+        let search = Search::new()
+            .range(0x10000..0x30000)
+            .threads(4)
+            .chunk_size(1000);
+
+        let states = search.collect(|rng| rng.rand() == 0);
+
+        assert!(!states.is_empty());
+        assert_eq!(states, sequential(0x10000..0x30000));
+    }
+
+    #[test]
+    fn test_progress_and_resume() {
+        let search = Search::new()
+            .range(0..0x10000)
+            .threads(3)
+            .chunk_size(0x1000);
+
+        let mut progresses = Vec::new();
+        let mut states = Vec::new();
+        let checkpoint = search.clone().range(0..0x8000).run(
+            |rng| rng.rand() == 0,
+            |state| states.push(state),
+            |progress| progresses.push(*progress),
+        );
+        assert_eq!(checkpoint, Checkpoint { next: 0x8000 });
+        assert_eq!(progresses.last().unwrap().checked, 0x8000);
+        assert!(progresses.windows(2).all(|w| w[0].checked < w[1].checked));
+
+        states.extend(search.resume(checkpoint).collect(|rng| rng.rand() == 0));
+        assert_eq!(states, sequential(0..0x10000));
+    }
+}
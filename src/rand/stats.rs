@@ -0,0 +1,355 @@
+use crate::rand::{RandomSource, Rng, State};
+
+/// The result of a statistical test.
+///
+/// `p_value` is the probability that a truly uniform and independent byte
+/// stream gives a statistic at least as extreme as `statistic`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TestResult {
+    pub statistic: f64,
+    pub p_value: f64,
+}
+
+/// The results of all the tests over `Rng::rand` values from a state.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub state: State,
+    pub samples: usize,
+    pub chi_square: TestResult,
+    pub serial_correlation: TestResult,
+    pub runs: TestResult,
+    pub gap: TestResult,
+    pub birthday_spacing: TestResult,
+}
+
+impl Report {
+    /// Return the smallest p-value among the tests, or NaN if any of them is.
+    pub fn min_p_value(&self) -> f64 {
+        let p_values = [
+            self.chi_square.p_value,
+            self.serial_correlation.p_value,
+            self.runs.p_value,
+            self.gap.p_value,
+            self.birthday_spacing.p_value,
+        ];
+        if p_values.iter().any(|p| p.is_nan()) {
+            return f64::NAN;
+        }
+        p_values.iter().cloned().fold(1.0, f64::min)
+    }
+}
+
+/// The number of birthdays in a trial of the birthday spacing test.
+const BIRTHDAYS: usize = 512;
+
+/// The fewest samples `run_all` accepts, i.e. one trial of the birthday
+/// spacing test.
+pub const MIN_SAMPLES: usize = 3 * BIRTHDAYS;
+
+/// Run every test over `samples` values, each from `state`, or return `None`
+/// if `samples` is less than `MIN_SAMPLES`.
+///
+/// The birthday spacing test needs at least about 10^5 samples to be reliable.
+pub fn run_all(state: State, samples: usize) -> Option<Report> {
+    if samples < MIN_SAMPLES {
+        return None;
+    }
+
+    Some(Report {
+        state,
+        samples,
+        chi_square: chi_square(&mut Rng::from(state), samples),
+        serial_correlation: serial_correlation(&mut Rng::from(state), samples),
+        runs: runs(&mut Rng::from(state), samples),
+        gap: gap(&mut Rng::from(state), samples, 0, 64),
+        birthday_spacing: birthday_spacing(&mut Rng::from(state), samples),
+    })
+}
+
+/// Pearson's chi-square test of the frequencies of the 256 values.
+pub fn chi_square<R: RandomSource>(rng: &mut R, samples: usize) -> TestResult {
+    let mut counts = [0_u64; 256];
+    for _ in 0..samples {
+        counts[rng.rand() as usize] += 1;
+    }
+
+    let expected = vec![samples as f64 / 256.0; 256];
+    let statistic = chi_square_statistic(&counts, &expected);
+    TestResult {
+        statistic,
+        p_value: chi_square_p_value(statistic, 255.0),
+    }
+}
+
+/// Knuth's serial correlation test between successive values.
+///
+/// The statistic is the correlation coefficient, and the p-value is two-sided
+/// by the normal approximation.
+pub fn serial_correlation<R: RandomSource>(rng: &mut R, samples: usize) -> TestResult {
+    assert!(samples > 2);
+
+    let values: Vec<f64> = (0..samples).map(|_| rng.rand() as f64).collect();
+    let n = samples as f64;
+    let sum: f64 = values.iter().sum();
+    let sum_sq: f64 = values.iter().map(|v| v * v).sum();
+    let sum_prod: f64 = (0..samples)
+        .map(|i| values[i] * values[(i + 1) % samples])
+        .sum();
+
+    let statistic = (n * sum_prod - sum * sum) / (n * sum_sq - sum * sum);
+    let mean = -1.0 / (n - 1.0);
+    let sd = n / ((n - 1.0) * (n - 2.0).sqrt());
+    TestResult {
+        statistic,
+        p_value: normal_two_sided_p_value((statistic - mean) / sd),
+    }
+}
+
+/// Wald-Wolfowitz runs test of values below and above 128.
+///
+/// The statistic is the number of runs, and the p-value is two-sided by the
+/// normal approximation.
+pub fn runs<R: RandomSource>(rng: &mut R, samples: usize) -> TestResult {
+    let highs: Vec<bool> = (0..samples).map(|_| rng.rand() >= 128).collect();
+    let n1 = highs.iter().filter(|h| **h).count() as f64;
+    let n2 = samples as f64 - n1;
+    let n = samples as f64;
+    let statistic = 1.0 + highs.windows(2).filter(|w| w[0] != w[1]).count() as f64;
+
+    let mean = 2.0 * n1 * n2 / n + 1.0;
+    let var = 2.0 * n1 * n2 * (2.0 * n1 * n2 - n) / (n * n * (n - 1.0));
+    TestResult {
+        statistic,
+        p_value: normal_two_sided_p_value((statistic - mean) / var.sqrt()),
+    }
+}
+
+/// Knuth's gap test of the values in `[lower, upper)`.
+///
+/// The lengths of the gaps between such values among `samples` values follow
+/// a geometric distribution, compared by a chi-square test. Long gaps are
+/// pooled so that every class expects at least 5 gaps.
+pub fn gap<R: RandomSource>(rng: &mut R, samples: usize, lower: u8, upper: u8) -> TestResult {
+    assert!(lower < upper);
+
+    let mut lengths = Vec::new();
+    let mut length = 0;
+    for _ in 0..samples {
+        let v = rng.rand();
+        if lower <= v && v < upper {
+            lengths.push(length);
+            length = 0;
+        } else {
+            length += 1;
+        }
+    }
+
+    let p = (upper - lower) as f64 / 256.0;
+    let gaps = lengths.len() as f64;
+    let mut classes = 1;
+    while classes < 64 && gaps * (1.0 - p).powi(classes as i32 + 1) >= 5.0 {
+        classes += 1;
+    }
+
+    // Classes of length 0, 1, ..., classes - 1 and the rest.
+    let mut counts = vec![0_u64; classes + 1];
+    for length in lengths {
+        counts[usize::min(length, classes)] += 1;
+    }
+    let mut expected: Vec<f64> = (0..classes)
+        .map(|r| gaps * p * (1.0 - p).powi(r as i32))
+        .collect();
+    expected.push(gaps * (1.0 - p).powi(classes as i32));
+
+    let statistic = chi_square_statistic(&counts, &expected);
+    TestResult {
+        statistic,
+        p_value: chi_square_p_value(statistic, classes as f64),
+    }
+}
+
+/// Marsaglia's birthday spacing test.
+///
+/// Each trial draws 512 birthdays in a year of 2^24 days from 3 values each,
+/// and counts the duplicated spacings, which follow Poisson(2). The counts of
+/// the `samples / 1536` trials are compared by a chi-square test.
+pub fn birthday_spacing<R: RandomSource>(rng: &mut R, samples: usize) -> TestResult {
+    const LAMBDA: f64 = 2.0;
+    const CLASSES: usize = 6;
+
+    let trials = samples / (3 * BIRTHDAYS);
+    let mut counts = [0_u64; CLASSES];
+    for _ in 0..trials {
+        let mut birthdays: Vec<u32> = (0..BIRTHDAYS)
+            .map(|_| u32::from_be_bytes([0, rng.rand(), rng.rand(), rng.rand()]))
+            .collect();
+        birthdays.sort_unstable();
+
+        let mut spacings: Vec<u32> = birthdays.windows(2).map(|w| w[1] - w[0]).collect();
+        spacings.sort_unstable();
+        let duplicates = spacings.windows(2).filter(|w| w[0] == w[1]).count();
+
+        counts[usize::min(duplicates, CLASSES - 1)] += 1;
+    }
+
+    let mut expected = Vec::new();
+    let mut pmf = (-LAMBDA).exp();
+    for k in 0..CLASSES - 1 {
+        expected.push(trials as f64 * pmf);
+        pmf *= LAMBDA / (k + 1) as f64;
+    }
+    expected.push(trials as f64 - expected.iter().sum::<f64>());
+
+    let statistic = chi_square_statistic(&counts, &expected);
+    TestResult {
+        statistic,
+        p_value: chi_square_p_value(statistic, (CLASSES - 1) as f64),
+    }
+}
+
+fn chi_square_statistic(counts: &[u64], expected: &[f64]) -> f64 {
+    counts
+        .iter()
+        .zip(expected.iter())
+        .map(|(o, e)| (*o as f64 - e).powi(2) / e)
+        .sum()
+}
+
+/// Return the upper tail probability of the chi-square distribution.
+fn chi_square_p_value(statistic: f64, df: f64) -> f64 {
+    gamma_q(df / 2.0, statistic / 2.0)
+}
+
+/// Return the two-sided tail probability of the standard normal distribution.
+fn normal_two_sided_p_value(z: f64) -> f64 {
+    // erfc(|z| / sqrt(2)) = Q(1/2, z^2 / 2)
+    gamma_q(0.5, z * z / 2.0)
+}
+
+/// The natural logarithm of the gamma function by the Lanczos approximation.
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.18009172947146,
+        -86.50532032941677,
+        24.01409824083091,
+        -1.231739572450155,
+        0.1208650973866179e-2,
+        -0.5395239384953e-5,
+    ];
+
+    let tmp = x + 5.5;
+    let tmp = tmp - (x + 0.5) * tmp.ln();
+    let mut ser = 1.000000000190015;
+    let mut y = x;
+    for c in COEFFICIENTS.iter() {
+        y += 1.0;
+        ser += c / y;
+    }
+    -tmp + (2.5066282746310005 * ser / x).ln()
+}
+
+/// The regularized upper incomplete gamma function Q(a, x).
+fn gamma_q(a: f64, x: f64) -> f64 {
+    const EPS: f64 = 1e-15;
+    const TINY: f64 = 1e-300;
+
+    if x <= 0.0 {
+        return 1.0;
+    }
+
+    let prefix = (-x + a * x.ln() - ln_gamma(a)).exp();
+    if x < a + 1.0 {
+        // The series of P(a, x).
+        let mut ap = a;
+        let mut del = 1.0 / a;
+        let mut sum = del;
+        while del.abs() > sum.abs() * EPS {
+            ap += 1.0;
+            del *= x / ap;
+            sum += del;
+        }
+        1.0 - sum * prefix
+    } else {
+        // The continued fraction of Q(a, x) by the modified Lentz's method.
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / TINY;
+        let mut d = 1.0 / b;
+        let mut h = d;
+        for i in 1..1000 {
+            let an = -(i as f64) * (i as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = b + an / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1.0 / d;
+            let del = d * c;
+            h *= del;
+            if (del - 1.0).abs() < EPS {
+                break;
+            }
+        }
+        prefix * h
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rand::SequenceRng;
+
+    use crate::testing;
+
+    fn assert_close(a: f64, b: f64) {
+        testing::assert_close(a, b, 1e-4);
+    }
+
+    #[test]
+    fn test_p_values() {
+        assert_close(normal_two_sided_p_value(0.0), 1.0);
+        assert_close(normal_two_sided_p_value(1.959964), 0.05);
+        assert_close(chi_square_p_value(3.841459, 1.0), 0.05);
+        assert_close(chi_square_p_value(293.2478, 255.0), 0.05);
+        assert_close(chi_square_p_value(11.0705, 5.0), 0.05);
+    }
+
+    #[test]
+    fn test_run_all() {
+        let report = run_all(State::default(), 200000).unwrap();
+
+        assert_eq!(report.samples, 200000);
+        assert!(report.min_p_value() > 0.001, "{:?}", report);
+
+        assert!(run_all(State::default(), MIN_SAMPLES - 1).is_none());
+        assert!(run_all(State::default(), 0).is_none());
+        let report = run_all(State::default(), MIN_SAMPLES).unwrap();
+        assert!(!report.birthday_spacing.p_value.is_nan());
+    }
+
+    #[test]
+    fn test_min_p_value_nan() {
+        let mut report = run_all(State::default(), MIN_SAMPLES).unwrap();
+        report.gap.p_value = f64::NAN;
+
+        assert!(report.min_p_value().is_nan());
+    }
+
+    #[test]
+    fn test_detect_bad_sequence() {
+        let values: Vec<u8> = (0..100000).map(|i| (i % 256) as u8).collect();
+
+        let result = runs(&mut SequenceRng::new(values.clone()), values.len());
+        assert!(result.p_value < 1e-6);
+
+        let result = gap(&mut SequenceRng::new(values.clone()), values.len(), 0, 64);
+        assert!(result.p_value < 1e-6);
+
+        let values: Vec<u8> = (0..100000).map(|i| (i % 2 * 128) as u8).collect();
+        let result = chi_square(&mut SequenceRng::new(values), 100000);
+        assert!(result.p_value < 1e-6);
+    }
+}
@@ -0,0 +1,306 @@
+use crate::rand::{RandomSource, Rng, State};
+
+use serde::Serialize;
+use std::fmt;
+
+/// A call to the RNG.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Call {
+    Transit,
+    Rand,
+    RandByMultiply { upper: u8 },
+    RandMultinomial { offset: u8, mask: u8 },
+}
+
+impl Call {
+    /// Return the address of the simulated subroutine, if any.
+    pub fn subroutine(&self) -> Option<u32> {
+        match self {
+            Call::Transit => None,
+            Call::Rand => Some(0x0012e3),
+            Call::RandByMultiply { .. } => Some(0x00133e),
+            Call::RandMultinomial { .. } => Some(0x0014d4),
+        }
+    }
+
+    /// Return the number of transitions consumed by the call.
+    pub fn transitions(&self) -> usize {
+        match self {
+            Call::Transit | Call::Rand | Call::RandByMultiply { .. } => 1,
+            Call::RandMultinomial { .. } => 16,
+        }
+    }
+}
+
+impl std::fmt::Display for Call {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Call::Transit => write!(f, "transit()"),
+            Call::Rand => write!(f, "rand()"),
+            Call::RandByMultiply { upper } => write!(f, "rand_by_multiply({})", upper),
+            Call::RandMultinomial { offset, mask } => {
+                write!(f, "rand_multinomial({}, {:#x})", offset, mask)
+            }
+        }
+    }
+}
+
+/// A record of a call to the RNG.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub call: Call,
+    /// The tag of the caller set by `TracingRng::set_tag` or by the caller
+    /// itself through `RandomSource::set_tag`.
+    pub tag: String,
+    pub before: State,
+    pub after: State,
+    /// The returned value, or `None` for `Call::Transit`.
+    pub value: Option<u8>,
+}
+
+/// A flat form of `TraceEntry` for CSV and JSON.
+#[derive(Serialize)]
+struct TraceRecord {
+    index: usize,
+    subroutine: String,
+    call: String,
+    tag: String,
+    before: String,
+    after: String,
+    value: Option<u8>,
+}
+
+impl TraceRecord {
+    fn new(index: usize, entry: &TraceEntry) -> Self {
+        Self {
+            index,
+            subroutine: entry
+                .call
+                .subroutine()
+                .map_or(String::new(), |addr| format!("0x{:06x}", addr)),
+            call: entry.call.to_string(),
+            tag: entry.tag.clone(),
+            before: entry.before.to_string(),
+            after: entry.after.to_string(),
+            value: entry.value,
+        }
+    }
+}
+
+/// A RNG which records every call with the tag of its caller.
+///
+/// This behaves exactly like the wrapped `Rng`, so it can replace one to find
+/// out which caller consumed which random numbers.
+#[derive(Debug, Clone, Default)]
+pub struct TracingRng {
+    rng: Rng,
+    tag: String,
+    entries: Vec<TraceEntry>,
+}
+
+impl TracingRng {
+    pub fn new(rng: Rng) -> Self {
+        Self {
+            rng,
+            tag: String::new(),
+            entries: vec![],
+        }
+    }
+
+    pub fn rng(&self) -> &Rng {
+        &self.rng
+    }
+
+    pub fn state(&self) -> State {
+        self.rng.state()
+    }
+
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// Set the tag attached to the following calls, e.g. "levelup Pow".
+    pub fn set_tag(&mut self, tag: &str) {
+        self.tag = tag.to_string();
+    }
+
+    pub fn entries(&self) -> &[TraceEntry] {
+        &self.entries
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    fn record<F: FnOnce(&mut Rng) -> Option<u8>>(&mut self, call: Call, f: F) -> Option<u8> {
+        let before = self.rng.state();
+        let value = f(&mut self.rng);
+        self.entries.push(TraceEntry {
+            call,
+            tag: self.tag.clone(),
+            before,
+            after: self.rng.state(),
+            value,
+        });
+        value
+    }
+
+    pub fn transit(&mut self) {
+        self.record(Call::Transit, |rng| {
+            rng.transit();
+            None
+        });
+    }
+
+    /// See `Rng::rand`.
+    pub fn rand(&mut self) -> u8 {
+        self.record(Call::Rand, |rng| Some(rng.rand())).unwrap()
+    }
+
+    /// See `Rng::rand_by_multiply`.
+    pub fn rand_by_multiply(&mut self, upper: u8) -> u8 {
+        self.record(Call::RandByMultiply { upper }, |rng| {
+            Some(rng.rand_by_multiply(upper))
+        })
+        .unwrap()
+    }
+
+    /// See `Rng::rand_multinomial`.
+    pub fn rand_multinomial(&mut self, offset: u8, mask: u8) -> u8 {
+        self.record(Call::RandMultinomial { offset, mask }, |rng| {
+            Some(rng.rand_multinomial(offset, mask))
+        })
+        .unwrap()
+    }
+
+    /// Export the entries as CSV with a header row.
+    pub fn to_csv(&self) -> String {
+        let mut wtr = csv::Writer::from_writer(vec![]);
+        for (i, entry) in self.entries.iter().enumerate() {
+            wtr.serialize(TraceRecord::new(i, entry)).unwrap();
+        }
+        String::from_utf8(wtr.into_inner().unwrap()).unwrap()
+    }
+
+    /// Export the entries as a JSON array.
+    pub fn to_json(&self) -> String {
+        let records: Vec<_> = self
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| TraceRecord::new(i, entry))
+            .collect();
+        serde_json::to_string(&records).unwrap()
+    }
+}
+
+impl RandomSource for TracingRng {
+    fn rand(&mut self) -> u8 {
+        TracingRng::rand(self)
+    }
+
+    fn rand_by_multiply(&mut self, upper: u8) -> u8 {
+        TracingRng::rand_by_multiply(self, upper)
+    }
+
+    fn rand_multinomial(&mut self, offset: u8, mask: u8) -> u8 {
+        TracingRng::rand_multinomial(self, offset, mask)
+    }
+
+    fn set_tag(&mut self, tag: fmt::Arguments) {
+        self.tag = tag.to_string();
+    }
+}
+
+impl From<Rng> for TracingRng {
+    fn from(rng: Rng) -> Self {
+        Self::new(rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace() {
+        let mut rng = TracingRng::default();
+
+        rng.set_tag("physical_damage");
+        let value = rng.rand_multinomial(6, 0xf);
+        rng.set_tag("levelup Pow");
+        rng.rand();
+        rng.transit();
+
+        let entries = rng.entries();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].call.subroutine(), Some(0x0014d4));
+        assert_eq!(entries[0].call.transitions(), 16);
+        assert_eq!(entries[0].tag, "physical_damage");
+        assert_eq!(entries[0].before, State::default());
+        assert_eq!(entries[0].after, State::default().advance(16));
+        assert_eq!(entries[0].value, Some(value));
+        assert_eq!(entries[1].tag, "levelup Pow");
+        assert_eq!(entries[2].call, Call::Transit);
+        assert_eq!(entries[2].value, None);
+        assert_eq!(rng.state(), State::default().advance(18));
+    }
+
+    #[test]
+    fn test_tag_by_caller() {
+        use crate::job::Job;
+        use crate::player::PlayerInit;
+
+        let mut player = PlayerInit {
+            lv: 1,
+            job: Job::Soldier,
+            ..Default::default()
+        }
+        .init();
+        let mut rng = TracingRng::default();
+        player.levelup_with(&mut rng);
+
+        let tags: Vec<&str> = rng.entries().iter().map(|e| e.tag.as_str()).collect();
+        assert_eq!(
+            tags,
+            vec![
+                "levelup Pow",
+                "levelup Spd",
+                "levelup Vit",
+                "levelup Int",
+                "levelup Lck"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_same_as_rng() {
+        let mut rng = Rng::default();
+        let mut tracing_rng = TracingRng::default();
+
+        assert_eq!(tracing_rng.rand(), rng.rand());
+        assert_eq!(tracing_rng.rand_by_multiply(16), rng.rand_by_multiply(16));
+        assert_eq!(
+            tracing_rng.rand_multinomial(136, 31),
+            rng.rand_multinomial(136, 31)
+        );
+        assert_eq!(tracing_rng.state(), rng.state());
+    }
+
+    #[test]
+    fn test_export() {
+        let mut rng = TracingRng::default();
+        rng.set_tag("test");
+        rng.rand();
+
+        assert_eq!(
+            rng.to_csv(),
+            "index,subroutine,call,tag,before,after,value\n\
+             0,0x0012e3,rand(),test,0xaae21259,0xe21259c7,199\n"
+        );
+        assert_eq!(
+            rng.to_json(),
+            r#"[{"index":0,"subroutine":"0x0012e3","call":"rand()","tag":"test","before":"0xaae21259","after":"0xe21259c7","value":199}]"#
+        );
+    }
+}
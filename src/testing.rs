@@ -0,0 +1,6 @@
+//! Helpers shared by the tests.
+
+/// Assert that `a` and `b` differ by less than `eps`.
+pub fn assert_close(a: f64, b: f64, eps: f64) {
+    assert!((a - b).abs() < eps, "{} != {}", a, b);
+}